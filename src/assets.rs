@@ -1,34 +1,59 @@
+use std::collections::HashMap;
 use ggez::graphics::{DrawMode, Drawable, Mesh, MeshBuilder};
-use ggez::graphics::Point2;
+use ggez::graphics::{Point2, Vector2};
 use ggez::Context;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
 pub enum DrawableAsset {
     Player,
+    Mob,
     Block,
     Wallh,
     Wallv,
     Bullet,
+    // Polygon meshes come from the level, so they are loaded by number with load_polygon.
+    Polygon(usize),
 }
 
 pub struct Assets {
     drawable: DrawableAssets,
+    polygons: HashMap<usize, (Vec<Vector2>, Mesh)>,
 }
 
 impl Assets {
     pub fn get_drawable(&self, drawable_asset: DrawableAsset) -> &Drawable {
-        self.drawable.get_drawable(drawable_asset)
+        match drawable_asset {
+            DrawableAsset::Polygon(index) => match self.polygons.get(&index) {
+                Some(&(_, ref mesh)) => mesh,
+                None => self.drawable.get_drawable(DrawableAsset::Block),
+            },
+            _ => self.drawable.get_drawable(drawable_asset),
+        }
     }
 
     pub fn new(ctx: &mut Context) -> Self {
         Self {
             drawable: DrawableAssets::new(ctx),
+            polygons: HashMap::new(),
+        }
+    }
+
+    // Build the index-th polygon mesh, unless the one already built has the same corners.
+    pub fn load_polygon(&mut self, ctx: &mut Context, index: usize, points: &[Vector2]) {
+        if let Some(&(ref loaded, _)) = self.polygons.get(&index) {
+            if loaded[..] == points[..] {
+                return;
+            }
         }
+        let mesh = DrawableAssets::polygon(ctx, points);
+        self.polygons.insert(index, (points.to_vec(), mesh));
     }
 }
 
 struct DrawableAssets {
     player: Mesh,
+    mob: Mesh,
     block: Mesh,
     wallh: Mesh,
     wallv: Mesh,
@@ -39,18 +64,20 @@ impl DrawableAssets {
     pub fn new(ctx: &mut Context) -> DrawableAssets {
         DrawableAssets {
             player: Self::rect(ctx, 10.0, 10.0),
+            mob: Self::circle(ctx, 5.0),
             block: Self::rect(ctx, 20.0, 20.0),
             wallh: Self::rect(ctx, 400.0, 20.0),
             wallv: Self::rect(ctx, 20.0, 400.0),
-            bullet: Self::rect(ctx, 2.0, 2.0),
+            bullet: Self::circle(ctx, 1.0),
         }
     }
 
+    //Drawn inside the same square as a rect of twice the radius, to match circle hitboxes.
     fn circle(ctx: &mut Context, radius: f32) -> Mesh {
         let mut builder = MeshBuilder::new();
         builder.circle(
             DrawMode::Fill,
-            Point2::new(-1.0 * radius, -1.0 * radius),
+            Point2::new(radius, -1.0 * radius),
             radius,
             radius / 200.0,
         );
@@ -71,13 +98,25 @@ impl DrawableAssets {
         builder.build(ctx).unwrap()
     }
 
+    fn polygon(ctx: &mut Context, points: &[Vector2]) -> Mesh {
+        let mut builder = MeshBuilder::new();
+        let points: Vec<Point2> = points
+            .iter()
+            .map(|point| Point2::new(point.x, -1.0 * point.y))
+            .collect();
+        builder.polygon(DrawMode::Fill, &points);
+        builder.build(ctx).unwrap()
+    }
+
     pub fn get_drawable(&self, drawable_asset: DrawableAsset) -> &Drawable {
         match drawable_asset {
             DrawableAsset::Player => &self.player,
+            DrawableAsset::Mob => &self.mob,
             DrawableAsset::Block => &self.block,
             DrawableAsset::Wallh => &self.wallh,
             DrawableAsset::Wallv => &self.wallv,
             DrawableAsset::Bullet => &self.bullet,
+            DrawableAsset::Polygon(_) => &self.block,
         }
     }
 }
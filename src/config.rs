@@ -0,0 +1,563 @@
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+use toml;
+use utils::FileError;
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub width: u32,
+    pub height: u32,
+    pub title: String,
+    pub vsync: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 450,
+            title: "gift".to_owned(),
+            vsync: true,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlayerConfig {
+    pub acceleration: f32,
+    pub max_speed: f32,
+    pub health: i32,
+    // 0 stops dead against walls, 1 bounces off at full speed.
+    pub elasticity: f32,
+    pub mass: f32,
+    // The player starts with the first, and can switch between all of them.
+    pub weapons: Vec<WeaponConfig>,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            acceleration: 2500.0,
+            max_speed: 250.0,
+            health: 30,
+            elasticity: 0.0,
+            mass: 1.0,
+            weapons: vec![
+                WeaponConfig::default(),
+                WeaponConfig {
+                    name: "shotgun".to_owned(),
+                    fire_rate: 0.6,
+                    speed: 400.0,
+                    spread: 30.0,
+                    pellets: 6,
+                    lifetime: 0.4,
+                    effects: vec![WeaponEffect::Damage(1), WeaponEffect::Knockback(250.0)],
+                    magazine: 6,
+                    reserve: Some(24),
+                    reload_time: 1.5,
+                },
+                WeaponConfig {
+                    name: "smg".to_owned(),
+                    fire_rate: 0.05,
+                    speed: 450.0,
+                    spread: 10.0,
+                    pellets: 1,
+                    lifetime: 0.8,
+                    effects: vec![WeaponEffect::Damage(1), WeaponEffect::Knockback(150.0)],
+                    magazine: 30,
+                    reserve: Some(120),
+                    reload_time: 1.2,
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeaponConfig {
+    pub name: String,
+    // Seconds between shots.
+    pub fire_rate: f32,
+    // How fast projectiles leave the weapon.
+    pub speed: f32,
+    // The angle in degrees that each projectile may stray from the aim, from one side to the
+    // other.
+    pub spread: f32,
+    // Projectiles per shot.
+    pub pellets: u32,
+    // Seconds before a projectile that hasn't hit anything vanishes.
+    pub lifetime: f32,
+    // What each projectile does to whatever it hits.
+    pub effects: Vec<WeaponEffect>,
+    // Shots before the weapon has to be reloaded, or 0 to never reload.
+    pub magazine: u32,
+    // Spare rounds to reload from. Leave out for as many as it takes.
+    pub reserve: Option<u32>,
+    // Seconds a reload takes.
+    pub reload_time: f32,
+}
+
+// Defaults to a pistol.
+impl Default for WeaponConfig {
+    fn default() -> Self {
+        Self {
+            name: "pistol".to_owned(),
+            fire_rate: 0.10,
+            speed: 500.0,
+            spread: 0.0,
+            pellets: 1,
+            lifetime: 1.0,
+            effects: vec![WeaponEffect::Damage(1), WeaponEffect::Knockback(400.0)],
+            magazine: 12,
+            reserve: None,
+            reload_time: 1.0,
+        }
+    }
+}
+
+impl WeaponConfig {
+    // Catches a weapon that could never fire, or would fire every step.
+    pub fn validate(&self) -> Result<(), String> {
+        let checks = [
+            (self.fire_rate > 0.0, "fire_rate", self.fire_rate),
+            (self.speed > 0.0, "speed", self.speed),
+            (self.lifetime > 0.0, "lifetime", self.lifetime),
+        ];
+        for &(valid, field, value) in checks.iter() {
+            if !valid {
+                return Err(format!("weapon {} {} must be positive ({})", self.name, field, value));
+            }
+        }
+        if self.pellets == 0 {
+            return Err(format!("weapon {} must fire at least 1 pellet", self.name));
+        }
+        if self.magazine == 0 && self.reserve.is_some() {
+            return Err(format!("weapon {} has a reserve but no magazine", self.name));
+        }
+        if !(self.reload_time >= 0.0) {
+            return Err(format!(
+                "weapon {} reload_time can't be negative ({})",
+                self.name, self.reload_time
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum WeaponEffect {
+    Damage(i32),
+    // Pushes what was hit along the projectile's path.
+    Knockback(f32),
+    // Leaves a timed status on what was hit.
+    Status(Status),
+}
+
+// A timed effect left on whatever is hit. Durations are in seconds, and damage over time is dealt
+// twice a second.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Status {
+    // Scales walking acceleration by multiplier. Doesn't stack. A stronger slow takes over for its
+    // own duration, and any other tops the timer back up to its duration.
+    Slow { multiplier: f32, duration: f32 },
+    // Damage over time. Each hit stacks up, to a limit.
+    Poison { damage: i32, duration: f32 },
+    // Damage over time. Doesn't stack, and a new hit works the same way as for Slow.
+    Burn { damage: i32, duration: f32 },
+    // Stops walking altogether. Doesn't stack, but a longer stun replaces a shorter one.
+    Stun { duration: f32 },
+}
+
+impl Status {
+    pub fn get_duration(&self) -> f32 {
+        match *self {
+            Status::Slow { duration, .. }
+            | Status::Poison { duration, .. }
+            | Status::Burn { duration, .. }
+            | Status::Stun { duration } => duration,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match *self {
+            Status::Slow { .. } => "slow",
+            Status::Poison { .. } => "poison",
+            Status::Burn { .. } => "burn",
+            Status::Stun { .. } => "stun",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MobConfig {
+    pub acceleration: f32,
+    pub drag: f32,
+    pub health: i32,
+    pub contact_damage: i32,
+    pub knockback: f32,
+    // Statuses left on the player on contact.
+    pub contact_statuses: Vec<Status>,
+    pub elasticity: f32,
+    pub mass: f32,
+    pub navigation: Navigation,
+    pub steering: SteeringConfig,
+}
+
+// How mobs find their way to the player when they can't see them.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Navigation {
+    // Each mob searches for a path to where it last saw the player.
+    Path,
+    // Every mob follows one shared flow field to the player, for large hordes.
+    FlowField,
+}
+
+impl Default for MobConfig {
+    fn default() -> Self {
+        Self {
+            acceleration: 1000.0,
+            drag: 2.0,
+            health: 5,
+            contact_damage: 1,
+            knockback: 2000.0,
+            contact_statuses: Vec::new(),
+            elasticity: 0.5,
+            mass: 1.0,
+            navigation: Navigation::Path,
+            steering: SteeringConfig::default(),
+        }
+    }
+}
+
+// How strongly each steering behaviour pulls a mob about. Each gives a push of at most unit
+// length, and the weighted sum is capped at the mob's full acceleration.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SteeringConfig {
+    // Towards wherever the mob's AI is sending it.
+    pub seek: f32,
+    // Mobs slow down within this distance of their target, instead of overshooting it.
+    pub arrive_radius: f32,
+    // Away from other mobs that are too close.
+    pub separation: f32,
+    // Towards the middle of the mobs nearby.
+    pub cohesion: f32,
+    // Away from walls up ahead.
+    pub avoidance: f32,
+    // How close other mobs have to be to count for separation and cohesion.
+    pub neighbour_radius: f32,
+}
+
+impl Default for SteeringConfig {
+    fn default() -> Self {
+        Self {
+            seek: 1.0,
+            arrive_radius: 10.0,
+            separation: 0.8,
+            cohesion: 0.1,
+            avoidance: 0.5,
+            neighbour_radius: 25.0,
+        }
+    }
+}
+
+// Mobs that hang back and shoot at the player. They get around the same way as the other mobs.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShooterConfig {
+    pub acceleration: f32,
+    pub drag: f32,
+    pub health: i32,
+    pub elasticity: f32,
+    pub mass: f32,
+    pub weapon: WeaponConfig,
+    pub steering: SteeringConfig,
+    // How far from the player a Shooter tries to stay while it's shooting.
+    pub preferred_distance: f32,
+    // How close a Shooter has to get before it starts shooting.
+    pub attack_range: f32,
+}
+
+impl Default for ShooterConfig {
+    fn default() -> Self {
+        Self {
+            acceleration: 800.0,
+            drag: 2.0,
+            health: 3,
+            elasticity: 0.5,
+            mass: 1.0,
+            weapon: WeaponConfig {
+                name: "rifle".to_owned(),
+                fire_rate: 0.8,
+                speed: 250.0,
+                effects: vec![
+                    WeaponEffect::Damage(1),
+                    WeaponEffect::Knockback(400.0),
+                    WeaponEffect::Status(Status::Slow {
+                        multiplier: 0.5,
+                        duration: 1.0,
+                    }),
+                ],
+                magazine: 5,
+                reload_time: 2.0,
+                ..WeaponConfig::default()
+            },
+            steering: SteeringConfig {
+                separation: 1.0,
+                cohesion: 0.0,
+                avoidance: 0.7,
+                neighbour_radius: 40.0,
+                ..SteeringConfig::default()
+            },
+            preferred_distance: 120.0,
+            attack_range: 200.0,
+        }
+    }
+}
+
+// Key names are SDL key names, e.g. "W", "Up", "Left Shift" or "Escape".
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BindingsConfig {
+    pub move_up: Vec<String>,
+    pub move_down: Vec<String>,
+    pub move_left: Vec<String>,
+    pub move_right: Vec<String>,
+    pub shoot_up: Vec<String>,
+    pub shoot_down: Vec<String>,
+    pub shoot_left: Vec<String>,
+    pub shoot_right: Vec<String>,
+    pub use_item: Vec<String>,
+    pub next_weapon: Vec<String>,
+    pub reload: Vec<String>,
+    pub weapon_1: Vec<String>,
+    pub weapon_2: Vec<String>,
+    pub weapon_3: Vec<String>,
+    pub reset: Vec<String>,
+    pub quick_save: Vec<String>,
+    pub quick_load: Vec<String>,
+    pub quit: Vec<String>,
+}
+
+fn keys(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+impl Default for BindingsConfig {
+    fn default() -> Self {
+        Self {
+            move_up: keys(&["W"]),
+            move_down: keys(&["S"]),
+            move_left: keys(&["A"]),
+            move_right: keys(&["D"]),
+            shoot_up: keys(&["Up"]),
+            shoot_down: keys(&["Down"]),
+            shoot_left: keys(&["Left"]),
+            shoot_right: keys(&["Right"]),
+            use_item: keys(&["E"]),
+            next_weapon: keys(&["Q"]),
+            reload: keys(&["F"]),
+            weapon_1: keys(&["1"]),
+            weapon_2: keys(&["2"]),
+            weapon_3: keys(&["3"]),
+            reset: keys(&["R"]),
+            quick_save: keys(&["F5"]),
+            quick_load: keys(&["F9"]),
+            quit: keys(&["Escape"]),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GamepadConfig {
+    // Stick deflection, from 0 to 1, that is ignored.
+    pub dead_zone: f32,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self { dead_zone: 0.2 }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameplayConfig {
+    pub player: PlayerConfig,
+    pub mob: MobConfig,
+    pub shooter: ShooterConfig,
+}
+
+impl GameplayConfig {
+    // Catches tuning that would break the game, like a zero max speed dividing by zero or a mob
+    // that starts out dead.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.player.max_speed > 0.0) {
+            return Err(format!(
+                "player max_speed must be positive ({})",
+                self.player.max_speed
+            ));
+        }
+        let healths = [
+            ("player", self.player.health),
+            ("mob", self.mob.health),
+            ("shooter", self.shooter.health),
+        ];
+        for &(name, health) in healths.iter() {
+            if health <= 0 {
+                return Err(format!("{} health must be positive ({})", name, health));
+            }
+        }
+        for weapon in self.player.weapons.iter().chain(Some(&self.shooter.weapon)) {
+            weapon.validate()?;
+        }
+        Ok(())
+    }
+}
+
+// The game can't step at zero ticks a second.
+pub fn validate_tick_rate(tick_rate: u32) -> Result<(), String> {
+    if tick_rate == 0 {
+        return Err("tick_rate must be at least 1".to_owned());
+    }
+    Ok(())
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DebugConfig {
+    // Label each mob with what it's currently doing.
+    pub show_mob_states: bool,
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            show_mob_states: true,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Simulation steps per second.
+    pub tick_rate: u32,
+    pub window: WindowConfig,
+    pub bindings: BindingsConfig,
+    pub gamepad: GamepadConfig,
+    pub gameplay: GameplayConfig,
+    pub debug: DebugConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tick_rate: 60,
+            window: WindowConfig::default(),
+            bindings: BindingsConfig::default(),
+            gamepad: GamepadConfig::default(),
+            gameplay: GameplayConfig::default(),
+            debug: DebugConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    // Missing files fall back to the defaults, but a file that exists and can't be read is an
+    // error.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Config, FileError> {
+        let mut contents = String::new();
+        match File::open(path) {
+            Ok(mut file) => file.read_to_string(&mut contents)?,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(FileError::Io(e)),
+        };
+        Config::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Config, FileError> {
+        let config: Config = toml::from_str(contents)?;
+        validate_tick_rate(config.tick_rate).map_err(FileError::Invalid)?;
+        config.gameplay.validate().map_err(FileError::Invalid)?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_toml_is_valid() {
+        let mut contents = String::new();
+        File::open("config.toml").unwrap().read_to_string(&mut contents).unwrap();
+        assert!(Config::parse(&contents).is_ok());
+    }
+
+    #[test]
+    fn zero_tick_rate_is_rejected() {
+        match Config::parse("tick_rate = 0") {
+            Err(FileError::Invalid(_)) => (),
+            _ => panic!("a zero tick rate was accepted"),
+        }
+    }
+
+    // The message from parsing a config that should have been rejected.
+    fn get_error(contents: &str) -> String {
+        match Config::parse(contents) {
+            Err(FileError::Invalid(reason)) => reason,
+            Err(e) => panic!("config didn't parse: {}", e),
+            Ok(_) => panic!("config was accepted:\n{}", contents),
+        }
+    }
+
+    #[test]
+    fn bad_weapons_are_rejected() {
+        let cases = [
+            ("fire_rate = 0.0", "weapon pistol fire_rate must be positive (0)"),
+            ("speed = -5.0", "weapon pistol speed must be positive (-5)"),
+            ("lifetime = 0.0", "weapon pistol lifetime must be positive (0)"),
+            ("pellets = 0", "weapon pistol must fire at least 1 pellet"),
+            ("magazine = 0\nreserve = 10", "weapon pistol has a reserve but no magazine"),
+            ("reload_time = -1.0", "weapon pistol reload_time can't be negative (-1)"),
+        ];
+        for &(field, reason) in cases.iter() {
+            let player = format!("[[gameplay.player.weapons]]\n{}", field);
+            assert_eq!(get_error(&player), reason);
+            let shooter = format!("[gameplay.shooter.weapon]\nname = \"pistol\"\n{}", field);
+            assert_eq!(get_error(&shooter), reason);
+        }
+        //A magazine of 0 means the weapon never has to reload.
+        assert!(Config::parse("[[gameplay.player.weapons]]\nmagazine = 0").is_ok());
+    }
+
+    #[test]
+    fn non_positive_health_is_rejected() {
+        for name in &["player", "mob", "shooter"] {
+            let contents = format!("[gameplay.{}]\nhealth = 0", name);
+            assert_eq!(get_error(&contents), format!("{} health must be positive (0)", name));
+        }
+    }
+
+    #[test]
+    fn zero_max_speed_is_rejected() {
+        for max_speed in &["0.0", "-1.0", "nan"] {
+            let contents = format!("[gameplay.player]\nmax_speed = {}", max_speed);
+            match Config::parse(&contents) {
+                Err(FileError::Invalid(_)) => (),
+                _ => panic!("max_speed {} was accepted", max_speed),
+            }
+        }
+    }
+}
@@ -3,19 +3,27 @@ use ggez::graphics::Color;
 use super::physics::ActorPhysics;
 use super::collision::Hitbox;
 use super::event::Event;
+use super::status::StatusEffects;
 use super::*;
 use assets::DrawableAsset;
 
+#[derive(Serialize, Deserialize)]
 pub struct BasicCuboid {
     walk_acceleration: f32,
     mesh: DrawableAsset,
     hitbox: Hitbox,
     physics: ActorPhysics,
+    #[serde(with = "::utils::point_serde")]
     position: Point2,
     health: i32,
     time_since_hurt: f32,
+    elasticity: f32,
+    mass: f32,
     id: ObjectID,
+    #[serde(with = "::utils::color_serde")]
     color: Color,
+    #[serde(default)]
+    statuses: StatusEffects,
 }
 
 impl BasicCuboid {
@@ -37,19 +45,55 @@ impl BasicCuboid {
             position,
             health,
             time_since_hurt: 300.0,
+            elasticity: 0.0,
+            mass: 1.0,
             id,
             color,
+            statuses: StatusEffects::default(),
         }
     }
+    pub fn with_elasticity(mut self, elasticity: f32) -> Self {
+        self.elasticity = elasticity;
+        self
+    }
+
+    pub fn with_hitbox(mut self, hitbox: Hitbox) -> Self {
+        self.hitbox = hitbox;
+        self
+    }
+
+    pub fn with_mass(mut self, mass: f32) -> Self {
+        self.mass = mass;
+        self
+    }
+
     pub fn get_health(&self) -> i32 {
         self.health
     }
+
+    pub fn get_statuses(&self) -> &StatusEffects {
+        &self.statuses
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.statuses.is_stunned()
+    }
+
+    // Directions shorter than a unit vector give partial acceleration, for analog input. Slows
+    // scale the acceleration down, and a stun stops it altogether.
     pub fn set_movement(&mut self, direction: Vector2) {
-        if let Some(unit_vector) = direction.try_normalize(0.0) {
+        if self.statuses.is_stunned() {
+            self.physics.set_acceleration(Vector2::zeros());
+            return;
+        }
+        let acceleration = self.walk_acceleration * self.statuses.get_speed_multiplier();
+        let magnitude = direction.norm();
+        if magnitude > 1.0 {
             self.physics
-                .set_acceleration(self.walk_acceleration * unit_vector);
+                .set_acceleration(acceleration * direction / magnitude);
         } else {
-            self.physics.set_acceleration(Vector2::new(0.0, 0.0));
+            self.physics
+                .set_acceleration(acceleration * direction);
         }
     }
 
@@ -67,16 +111,25 @@ impl HasHitbox for BasicCuboid {
 }
 
 impl HasPhysics for BasicCuboid {
-    fn recieve_collision(&mut self, _dt: f32, collision: collision::Collision) {
-        let p = collision.get_penetration();
-        self.position -= p;
-        let mut v = self.physics.get_velocity();
-        match (p.x == 0.0, p.y == 0.0) {
-            (false, true) => v.x = 0.0,
-            (true, false) => v.y = 0.0,
-            _ => (),
+    fn get_elasticity(&self) -> f32 {
+        self.elasticity
+    }
+
+    fn get_velocity(&self) -> Vector2 {
+        self.physics.get_velocity()
+    }
+
+    fn get_inverse_mass(&self) -> f32 {
+        if self.mass > 0.0 {
+            1.0 / self.mass
+        } else {
+            0.0
         }
-        self.physics.set_velocity(v);
+    }
+
+    fn recieve_collision(&mut self, _dt: f32, collision: collision::Collision) {
+        self.position -= collision.get_penetration();
+        self.physics.add_impulse(collision.get_velocity_change());
     }
 }
 
@@ -106,6 +159,7 @@ impl CanRecieveEvents for BasicCuboid {
                 from,
                 magnitude,
             } =>  { self.physics.add_impulse(magnitude*(self.position - from).normalize()) },
+            Event::Status(status) => self.statuses.apply(status),
         }
     }
 }
@@ -125,6 +179,11 @@ impl Object for BasicCuboid {
         if self.time_since_hurt < 200.0 {
             self.time_since_hurt += dt;
         }
+        let damage = self.statuses.step(dt);
+        if damage > 0 {
+            self.health -= damage;
+            self.time_since_hurt = 0.0;
+        }
     }
 
     fn should_delete(&self) -> bool {
@@ -0,0 +1,218 @@
+use super::CanSetMoveTarget;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BehaviourState {
+    Idle,
+    Patrol,
+    Chase,
+    Attack,
+    Flee,
+    Stunned,
+}
+
+// What a mob knows about its situation when deciding what to do.
+#[derive(Clone, Copy)]
+pub struct Senses {
+    // From the mob's center to the player's.
+    pub distance: f32,
+    pub can_see_player: bool,
+    // How much of its health the mob has left, from 0 to 1.
+    pub health: f32,
+    pub stunned: bool,
+    // How close the mob has to be to the player to attack, see HasBehaviour::get_attack_range.
+    pub attack_range: f32,
+}
+
+#[derive(Clone, Copy)]
+pub enum Condition {
+    SeesPlayer,
+    CantSeePlayer,
+    // Hasn't seen the player for at least this many seconds.
+    LostPlayerFor(f32),
+    CloserThan(f32),
+    FurtherThan(f32),
+    // Closer than the mob's attack range.
+    InAttackRange,
+    // Further than the mob's attack range plus this much.
+    OutOfAttackRange(f32),
+    HealthBelow(f32),
+    IsStunned,
+    NotStunned,
+    // Has been in the current state for at least this many seconds.
+    After(f32),
+}
+
+// Go to `to` from any of the states in `from` once all of the conditions hold. An empty `from`
+// means any state.
+pub struct Transition {
+    pub from: &'static [BehaviourState],
+    pub to: BehaviourState,
+    pub when: &'static [Condition],
+}
+
+pub trait HasBehaviour: CanSetMoveTarget {
+    fn get_behaviour(&self) -> &Behaviour;
+    fn get_behaviour_mut(&mut self) -> &mut Behaviour;
+
+    // This mob type's transition table. The first transition that applies is taken.
+    fn get_transitions(&self) -> &'static [Transition];
+
+    fn get_health_fraction(&self) -> f32;
+
+    fn is_stunned(&self) -> bool {
+        false
+    }
+
+    // How far from the player the mob likes to fight. Zero to go right up to them.
+    fn get_preferred_distance(&self) -> f32 {
+        0.0
+    }
+
+    // For the InAttackRange and OutOfAttackRange conditions, so that the range can come from
+    // config when the transition table can't.
+    fn get_attack_range(&self) -> f32 {
+        0.0
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Behaviour {
+    state: BehaviourState,
+    time_in_state: f32,
+    time_since_seen: f32,
+}
+
+impl Behaviour {
+    pub fn new() -> Self {
+        Self {
+            state: BehaviourState::Idle,
+            time_in_state: 0.0,
+            time_since_seen: 300.0,
+        }
+    }
+
+    pub fn get_state(&self) -> BehaviourState {
+        self.state
+    }
+
+    pub fn get_time_in_state(&self) -> f32 {
+        self.time_in_state
+    }
+
+    // Advance the timers and take the first transition out of the current state whose conditions
+    // all hold. Returns whether the state changed.
+    pub fn update(&mut self, dt: f32, senses: &Senses, transitions: &[Transition]) -> bool {
+        self.time_in_state += dt;
+        if senses.can_see_player {
+            self.time_since_seen = 0.0;
+        } else if self.time_since_seen < 200.0 {
+            self.time_since_seen += dt;
+        }
+
+        for transition in transitions {
+            if transition.to == self.state
+                || !(transition.from.is_empty() || transition.from.contains(&self.state))
+            {
+                continue;
+            }
+            if transition.when.iter().all(|c| self.holds(c, senses)) {
+                self.state = transition.to;
+                self.time_in_state = 0.0;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn holds(&self, condition: &Condition, senses: &Senses) -> bool {
+        match *condition {
+            Condition::SeesPlayer => senses.can_see_player,
+            Condition::CantSeePlayer => !senses.can_see_player,
+            Condition::LostPlayerFor(time) => self.time_since_seen >= time,
+            Condition::CloserThan(distance) => senses.distance < distance,
+            Condition::FurtherThan(distance) => senses.distance > distance,
+            Condition::InAttackRange => senses.distance < senses.attack_range,
+            Condition::OutOfAttackRange(margin) => senses.distance > senses.attack_range + margin,
+            Condition::HealthBelow(health) => senses.health < health,
+            Condition::IsStunned => senses.stunned,
+            Condition::NotStunned => !senses.stunned,
+            Condition::After(time) => self.time_in_state >= time,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::BehaviourState::*;
+    use super::Condition::*;
+
+    const TRANSITIONS: &[Transition] = &[
+        Transition { from: &[], to: Stunned, when: &[IsStunned] },
+        Transition { from: &[Stunned], to: Idle, when: &[NotStunned] },
+        Transition { from: &[Idle], to: Chase, when: &[SeesPlayer] },
+        Transition { from: &[Chase], to: Flee, when: &[HealthBelow(0.3)] },
+        Transition { from: &[Flee], to: Chase, when: &[After(2.0)] },
+    ];
+
+    fn senses(can_see_player: bool, health: f32, stunned: bool) -> Senses {
+        Senses {
+            distance: 50.0,
+            can_see_player,
+            health,
+            stunned,
+            attack_range: 0.0,
+        }
+    }
+
+    #[test]
+    fn stun_applies_from_any_state() {
+        let mut behaviour = Behaviour::new();
+        assert!(behaviour.update(0.1, &senses(true, 1.0, false), TRANSITIONS));
+        assert_eq!(behaviour.get_state(), Chase);
+        assert!(behaviour.update(0.1, &senses(true, 1.0, true), TRANSITIONS));
+        assert_eq!(behaviour.get_state(), Stunned);
+        assert!(behaviour.update(0.1, &senses(true, 1.0, false), TRANSITIONS));
+        assert_eq!(behaviour.get_state(), Idle);
+        assert!(behaviour.update(0.1, &senses(false, 1.0, true), TRANSITIONS));
+        assert_eq!(behaviour.get_state(), Stunned);
+    }
+
+    #[test]
+    fn flee_turns_back_to_chase_after_a_while() {
+        let mut behaviour = Behaviour::new();
+        behaviour.update(0.1, &senses(true, 1.0, false), TRANSITIONS);
+        assert!(behaviour.update(0.1, &senses(true, 0.1, false), TRANSITIONS));
+        assert_eq!(behaviour.get_state(), Flee);
+        assert!(!behaviour.update(1.0, &senses(true, 0.1, false), TRANSITIONS));
+        assert!(!behaviour.update(0.5, &senses(true, 0.1, false), TRANSITIONS));
+        assert_eq!(behaviour.get_state(), Flee);
+        assert!(behaviour.update(0.5, &senses(true, 0.1, false), TRANSITIONS));
+        assert_eq!(behaviour.get_state(), Chase);
+        assert_eq!(behaviour.get_time_in_state(), 0.0);
+    }
+
+    #[test]
+    fn transitions_to_the_current_state_are_skipped() {
+        let mut behaviour = Behaviour::new();
+        behaviour.update(0.1, &senses(true, 1.0, true), TRANSITIONS);
+        assert_eq!(behaviour.get_state(), Stunned);
+        //Still stunned, so the from-any stun applies again, but mustn't restart the state.
+        for _ in 0..10 {
+            assert!(!behaviour.update(0.1, &senses(true, 1.0, true), TRANSITIONS));
+        }
+        assert_eq!(behaviour.get_state(), Stunned);
+        assert!((behaviour.get_time_in_state() - 1.0).abs() < 1e-5);
+
+        //Skipping the first transition still leaves the ones after it.
+        const CHASE_OR_ATTACK: &[Transition] = &[
+            Transition { from: &[], to: Chase, when: &[SeesPlayer] },
+            Transition { from: &[Chase], to: Attack, when: &[CloserThan(100.0)] },
+        ];
+        let mut behaviour = Behaviour::new();
+        assert!(behaviour.update(0.1, &senses(true, 1.0, false), CHASE_OR_ATTACK));
+        assert_eq!(behaviour.get_state(), Chase);
+        assert!(behaviour.update(0.1, &senses(true, 1.0, false), CHASE_OR_ATTACK));
+        assert_eq!(behaviour.get_state(), Attack);
+    }
+}
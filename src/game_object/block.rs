@@ -1,16 +1,28 @@
 use ggez::graphics::{Point2, Vector2};
 use ggez::graphics::Color;
-use super::collision::Hitbox;
+use super::collision::{cross, Hitbox};
 use super::Object;
+use super::ObjectID;
 use super::HasHitbox;
 use super::HasPhysics;
 use super::Renderable;
+use super::CanRecieveEvents;
+use super::event::Event;
 use assets::DrawableAsset;
 
+#[derive(Serialize, Deserialize)]
 pub struct Block {
     mesh: DrawableAsset,
+    #[serde(with = "::utils::point_serde")]
     position: Point2,
     hitbox: Hitbox,
+    #[serde(with = "::utils::point_serde")]
+    scale: Point2,
+    // Blocks with health take damage from bullets, the rest just stop them.
+    #[serde(default)]
+    health: Option<i32>,
+    #[serde(default)]
+    elasticity: f32,
 }
 
 impl Block {
@@ -19,14 +31,68 @@ impl Block {
             mesh: DrawableAsset::Block,
             position: Point2::new(x, y),
             hitbox: Hitbox::new(Vector2::new(20.0, 20.0)),
+            scale: Point2::new(1.0, 1.0),
+            health: None,
+            elasticity: 0.0,
         }
     }
 
+    // A block of any size. Rendered by stretching the 20x20 block mesh.
+    pub fn with_size(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            mesh: DrawableAsset::Block,
+            position: Point2::new(x, y),
+            hitbox: Hitbox::new(Vector2::new(width, height)),
+            scale: Point2::new(width / 20.0, height / 20.0),
+            health: None,
+            elasticity: 0.0,
+        }
+    }
+
+    // A convex obstacle from its corners in the world, wound either way. Its mesh is the index-th
+    // polygon mesh, which has to be loaded into Assets from the hitbox before it's drawn.
+    pub fn polygon(index: usize, points: &[Point2]) -> Self {
+        let mut low = points[0];
+        for point in points {
+            low.x = low.x.min(point.x);
+            low.y = low.y.min(point.y);
+        }
+        let mut local: Vec<Vector2> = points.iter().map(|point| point - low).collect();
+        let mut area = 0.0;
+        for (i, point) in local.iter().enumerate() {
+            area += cross(*point, local[(i + 1) % local.len()]);
+        }
+        if area < 0.0 {
+            local.reverse();
+        }
+        Self {
+            mesh: DrawableAsset::Polygon(index),
+            position: low,
+            hitbox: Hitbox::polygon(local),
+            scale: Point2::new(1.0, 1.0),
+            health: None,
+            elasticity: 0.0,
+        }
+    }
+
+    pub fn with_health(mut self, health: i32) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    pub fn with_elasticity(mut self, elasticity: f32) -> Self {
+        self.elasticity = elasticity;
+        self
+    }
+
     pub fn wallh(x: f32, y: f32) -> Self {
         Self {
             mesh: DrawableAsset::Wallh,
             position: Point2::new(x, y),
             hitbox: Hitbox::new(Vector2::new(400.0, 20.0)),
+            scale: Point2::new(1.0, 1.0),
+            health: None,
+            elasticity: 0.0,
         }
     }
 
@@ -35,6 +101,9 @@ impl Block {
             mesh: DrawableAsset::Wallv,
             position: Point2::new(x, y),
             hitbox: Hitbox::new(Vector2::new(20.0, 400.0)),
+            scale: Point2::new(1.0, 1.0),
+            health: None,
+            elasticity: 0.0,
         }
     }
 
@@ -48,6 +117,10 @@ impl Renderable for Block {
     fn get_color(&self) -> Option<Color> {
         Some(Color::new(0.3, 0.7, 0.3, 0.7))
     }
+
+    fn get_scale(&self) -> Point2 {
+        self.scale
+    }
 }
 
 impl HasHitbox for Block {
@@ -56,10 +129,36 @@ impl HasHitbox for Block {
     }
 }
 
-impl HasPhysics for Block {}
+impl HasPhysics for Block {
+    fn get_elasticity(&self) -> f32 {
+        self.elasticity
+    }
+}
+
+impl CanRecieveEvents for Block {
+    fn recieve_event(&mut self, _dt: f32, event: Event) {
+        if let Some(ref mut health) = self.health {
+            if let Event::Damage(damage) = event {
+                *health -= damage;
+            }
+        }
+    }
+}
 
 impl Object for Block {
     fn get_position(&self) -> Point2 {
         self.position
     }
+
+    // Not the mobs' id, so that their bullets don't go through walls.
+    fn get_id(&self) -> ObjectID {
+        ObjectID::new(2)
+    }
+
+    fn should_delete(&self) -> bool {
+        match self.health {
+            Some(health) => health <= 0,
+            None => false,
+        }
+    }
 }
@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use ggez::graphics::{Point2, Vector2};
+use super::HasHitbox;
+use super::collision::get_swept_bounds;
+
+// Side length of a grid cell. A little over the size of a mob.
+pub const CELL_SIZE: f32 = 32.0;
+
+type Cell = (i32, i32);
+
+// The cells covered by a box, from its bottom left to its top right cell.
+#[derive(Clone, Copy)]
+struct CellRange {
+    min: Cell,
+    max: Cell,
+}
+
+// A uniform grid that buckets hitboxes by the cells they cover, so that only objects sharing a
+// cell need to be tested against each other. Objects are referred to by their index in the list
+// the grid was built from.
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<usize>>,
+    ranges: Vec<Option<CellRange>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            ranges: Vec::new(),
+        }
+    }
+
+    pub fn from_list<T: HasHitbox>(cell_size: f32, list: &[T]) -> Self {
+        let mut grid = SpatialHash::new(cell_size);
+        for (index, object) in list.iter().enumerate() {
+            grid.insert_object(index, object);
+        }
+        grid
+    }
+
+    fn get_cell(&self, point: Point2) -> Cell {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn get_range(&self, position: Point2, size: Vector2) -> CellRange {
+        CellRange {
+            min: self.get_cell(position),
+            max: self.get_cell(position + size),
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, position: Point2, size: Vector2) {
+        self.remove(index);
+        let range = self.get_range(position, size);
+        for x in range.min.0..range.max.0 + 1 {
+            for y in range.min.1..range.max.1 + 1 {
+                self.cells.entry((x, y)).or_insert_with(Vec::new).push(index);
+            }
+        }
+        if self.ranges.len() <= index {
+            self.ranges.resize(index + 1, None);
+        }
+        self.ranges[index] = Some(range);
+    }
+
+    pub fn insert_object<T: HasHitbox>(&mut self, index: usize, object: &T) {
+        let (position, size) = get_swept_bounds(object);
+        self.insert(index, position, size);
+    }
+
+    // Take an object out of the cells it was inserted into, dropping cells left empty so that the
+    // map doesn't fill up with them as things move about.
+    fn remove(&mut self, index: usize) {
+        let range = match self.ranges.get_mut(index) {
+            Some(range) => range.take(),
+            None => None,
+        };
+        if let Some(range) = range {
+            for x in range.min.0..range.max.0 + 1 {
+                for y in range.min.1..range.max.1 + 1 {
+                    let is_empty = match self.cells.get_mut(&(x, y)) {
+                        Some(cell) => {
+                            cell.retain(|i| *i != index);
+                            cell.is_empty()
+                        }
+                        None => false,
+                    };
+                    if is_empty {
+                        self.cells.remove(&(x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    // Every index sharing a cell with the box, in ascending order and without duplicates.
+    pub fn query(&self, position: Point2, size: Vector2) -> Vec<usize> {
+        let range = self.get_range(position, size);
+        let mut found = Vec::new();
+        for x in range.min.0..range.max.0 + 1 {
+            for y in range.min.1..range.max.1 + 1 {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    found.extend_from_slice(cell);
+                }
+            }
+        }
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    pub fn query_object<T: HasHitbox>(&self, object: &T) -> Vec<usize> {
+        let (position, size) = get_swept_bounds(object);
+        self.query(position, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ggez::graphics::Color;
+    use rand::Rng;
+    use config::{MobConfig, PlayerConfig};
+    use game_object::*;
+    use game_object::behaviour::HasBehaviour;
+    use game_object::block::Block;
+    use game_object::bullet::Bullet;
+    use game_object::event::Event;
+    use game_object::collision::{object_vec_physics, resolve_collision, sweep, vec_physics,
+                                 vec_vec_physics};
+    use game_object::mob::{Dummy, Mob};
+    use game_object::player::Player;
+    use utils::SavableRng;
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    struct Layout {
+        player: Player,
+        mobs: Vec<Mob>,
+        blocks: Vec<Block>,
+        bullets: Vec<Bullet>,
+    }
+
+    // A crowd of mobs, some walls and a spray of bullets, fast enough that some need sweeping.
+    fn random_layout(seed: u32) -> Layout {
+        let mut rng = SavableRng::new(seed);
+        let mut point = |rng: &mut SavableRng| {
+            Point2::new(rng.gen_range(-150.0, 150.0), rng.gen_range(-150.0, 150.0))
+        };
+        let player = Player::new(point(&mut rng), &PlayerConfig::default());
+        let mobs = (0..40)
+            .map(|_| Mob::Dummy(Dummy::new(point(&mut rng), &MobConfig::default())))
+            .collect();
+        let blocks = (0..8)
+            .map(|_| {
+                let corner = point(&mut rng);
+                let (width, height) = (rng.gen_range(5.0, 80.0), rng.gen_range(5.0, 80.0));
+                Block::with_size(corner.x, corner.y, width, height).with_health(3)
+            })
+            .collect();
+        let bullets = (0..60)
+            .map(|_| {
+                let origin = point(&mut rng);
+                let angle: f32 = rng.gen_range(0.0, 6.28);
+                let speed = rng.gen_range(100.0, 3000.0);
+                let heading = Vector2::new(angle.cos(), angle.sin());
+                let effects = vec![Event::Damage(1), Event::Impulse(400.0 * heading)];
+                let color = Color::new(1.0, 1.0, 1.0, 1.0);
+                Bullet::new(origin, speed * heading, color, Vec::new(), effects)
+            })
+            .collect();
+        Layout {
+            player,
+            mobs,
+            blocks,
+            bullets,
+        }
+    }
+
+    // The all-pairs loops the grid stands in for, visiting pairs in the same order.
+    fn brute_vec_physics<T: HasPhysics>(list: &mut Vec<T>) {
+        for x in 0..list.len() {
+            let (object_1, rest) = list[x..].split_first_mut().unwrap();
+            for object_2 in rest.iter_mut() {
+                resolve_collision(DT, object_1, object_2);
+            }
+        }
+    }
+
+    fn brute_object_vec_physics<T: HasPhysics, U: HasPhysics>(object_1: &mut T, list: &mut [U]) {
+        for object_2 in list.iter_mut() {
+            resolve_collision(DT, object_1, object_2);
+        }
+    }
+
+    fn brute_first_hits<T: HasHitbox, U: HasCollisionEvents>(object_1: &T, list: &mut [U]) {
+        for object_2 in list.iter_mut() {
+            if object_2.can_hit(object_1.get_id()) {
+                if let Some(hit) = sweep(object_1, object_2) {
+                    object_2.offer_hit(hit.get_time());
+                }
+            }
+        }
+    }
+
+    fn brute_collision_events<T, U>(object_1: &mut T, list: &mut [U])
+    where
+        T: HasHitbox + CanRecieveEvents,
+        U: HasCollisionEvents,
+    {
+        for object_2 in list.iter_mut() {
+            if let Some(hit) = sweep(object_1, object_2) {
+                if object_2.accepts_hit(hit.get_time()) {
+                    for event in object_2.create_collision_event(object_1.get_id()) {
+                        object_1.recieve_event(DT, event);
+                    }
+                }
+            }
+        }
+    }
+
+    // One tick of everything touching everything, in the order World runs it.
+    fn step(layout: &mut Layout, use_grid: bool) {
+        let Layout {
+            ref mut player,
+            ref mut mobs,
+            ref mut blocks,
+            ref mut bullets,
+        } = *layout;
+        player.step(DT);
+        for object in mobs.iter_mut() {
+            object.step(DT);
+        }
+        for object in bullets.iter_mut() {
+            object.step(DT);
+        }
+        if use_grid {
+            vec_vec_first_hits(mobs, bullets);
+            object_vec_first_hits(player, bullets);
+            vec_vec_first_hits(blocks, bullets);
+            vec_vec_collision_events(DT, mobs, bullets);
+            vec_vec_collision_events(DT, blocks, bullets);
+            object_vec_collision_events(DT, player, bullets);
+            object_vec_collision_events(DT, player, mobs);
+            vec_vec_physics(DT, mobs, blocks);
+            vec_physics(DT, mobs);
+            object_vec_physics(DT, player, blocks);
+            object_vec_physics(DT, player, mobs);
+        } else {
+            for mob in mobs.iter() {
+                brute_first_hits(mob, bullets);
+            }
+            brute_first_hits(player, bullets);
+            for block in blocks.iter() {
+                brute_first_hits(block, bullets);
+            }
+            for mob in mobs.iter_mut() {
+                brute_collision_events(mob, bullets);
+            }
+            for block in blocks.iter_mut() {
+                brute_collision_events(block, bullets);
+            }
+            brute_collision_events(player, bullets);
+            brute_collision_events(player, mobs);
+            for mob in mobs.iter_mut() {
+                brute_object_vec_physics(mob, blocks);
+            }
+            brute_vec_physics(mobs);
+            brute_object_vec_physics(player, blocks);
+            brute_object_vec_physics(player, mobs);
+        }
+        bullets.retain(|bullet| !bullet.should_delete());
+        blocks.retain(|block| !block.should_delete());
+    }
+
+    // Everything the events and physics can change, to compare exactly.
+    fn get_state(layout: &Layout) -> Vec<f32> {
+        let mut state = Vec::new();
+        let mut add = |position: Point2, velocity: Vector2| {
+            state.extend_from_slice(&[position.x, position.y, velocity.x, velocity.y])
+        };
+        add(layout.player.get_position(), layout.player.get_velocity());
+        for mob in &layout.mobs {
+            add(mob.get_position(), mob.get_velocity());
+        }
+        for bullet in &layout.bullets {
+            add(bullet.get_position(), Vector2::zeros());
+        }
+        state.push(layout.player.get_health() as f32);
+        state.extend(layout.mobs.iter().map(|mob| mob.get_health_fraction()));
+        state.push(layout.blocks.len() as f32);
+        state.push(layout.bullets.len() as f32);
+        state
+    }
+
+    #[test]
+    fn grid_matches_all_pairs() {
+        let mut bullets_used = 0;
+        for seed in 1..11 {
+            let mut grid = random_layout(seed);
+            let mut brute = random_layout(seed);
+            for tick in 0..30 {
+                step(&mut grid, true);
+                step(&mut brute, false);
+                assert!(
+                    get_state(&grid) == get_state(&brute),
+                    "seed {} differs at tick {}",
+                    seed,
+                    tick
+                );
+            }
+            bullets_used += 60 - grid.bullets.len();
+        }
+        // Bullets only run out of lifetime after a second, so these all hit something.
+        assert!(bullets_used > 0);
+    }
+
+    #[test]
+    fn moving_objects_leave_no_empty_cells() {
+        let (small, large) = (Vector2::new(10.0, 10.0), Vector2::new(100.0, 100.0));
+        let mut grid = SpatialHash::new(CELL_SIZE);
+        let mut position = Point2::new(0.0, 0.0);
+        for step in 0..100 {
+            position = Point2::new(step as f32 * 7.0, step as f32 * -3.0);
+            grid.insert(0, position, small);
+            grid.insert(1, Point2::new(0.0, 0.0), large);
+        }
+        let mut fresh = SpatialHash::new(CELL_SIZE);
+        fresh.insert(0, position, small);
+        fresh.insert(1, Point2::new(0.0, 0.0), large);
+        assert!(grid.cells.values().all(|cell| !cell.is_empty()));
+        assert_eq!(grid.cells.len(), fresh.cells.len());
+    }
+}
@@ -10,27 +10,41 @@ use super::Renderable;
 use super::ObjectID;
 use assets::DrawableAsset;
 
+#[derive(Serialize, Deserialize)]
 pub struct Bullet {
     hitbox: Hitbox,
     mesh: DrawableAsset,
+    #[serde(with = "::utils::point_serde")]
     position: Point2,
+    #[serde(with = "::utils::point_serde")]
+    last_position: Point2,
+    // Time of impact of the first thing in the bullet's path this step.
+    #[serde(default)]
+    first_hit: Option<f32>,
     physics: ActorPhysics,
     lifetime: f32,
     max_lifetime: f32,
     effects: Vec<Event>,
+    #[serde(with = "::utils::color_serde")]
     color: Color,
     whitelist: Vec<ObjectID>,
 }
 
 impl Bullet {
-    pub fn new(position: Point2, velocity: Vector2, color: Color, whitelist: Vec<ObjectID>) -> Self {
-        let mut effects = Vec::new();
-        effects.push(Event::Damage(1));
-        effects.push(Event::Impulse(400.0 * velocity.normalize()));
+    // Effects are the events set off in whatever the bullet hits.
+    pub fn new(
+        position: Point2,
+        velocity: Vector2,
+        color: Color,
+        whitelist: Vec<ObjectID>,
+        effects: Vec<Event>,
+    ) -> Self {
         let mut bullet = Bullet {
-            hitbox: Hitbox::new(Vector2::new(2.0, 2.0)),
+            hitbox: Hitbox::circle(1.0),
             mesh: DrawableAsset::Bullet,
             position,
+            last_position: position,
+            first_hit: None,
             physics: ActorPhysics::new(0.0),
             lifetime: 0.0,
             max_lifetime: 1.0,
@@ -42,6 +56,11 @@ impl Bullet {
         bullet
     }
 
+    pub fn with_lifetime(mut self, lifetime: f32) -> Self {
+        self.max_lifetime = lifetime;
+        self
+    }
+
     fn update_position(&mut self, dt: f32) {
         if self.physics.get_velocity().norm() > 10.0 {
             self.position += self.physics.get_velocity() * dt;
@@ -73,19 +92,43 @@ impl Renderable for Bullet {
 
 impl HasCollisionEvents for Bullet {
     fn create_collision_event(&mut self, id: ObjectID) -> Vec<Event> {
-        if !self.get_whitelist().iter().any(|x| *x == id) {
+        if self.can_hit(id) {
             self.mark_for_deletion();
             self.get_effects()
         } else {
             Vec::new()
         }
     }
+
+    fn can_hit(&self, id: ObjectID) -> bool {
+        !self.get_whitelist().iter().any(|x| *x == id)
+    }
+
+    fn offer_hit(&mut self, time: f32) {
+        self.first_hit = match self.first_hit {
+            Some(first) if first <= time => Some(first),
+            _ => Some(time),
+        };
+    }
+
+    //Only the first thing along the bullet's path this step gets hit, so that a fast bullet can't
+    //reach through a wall to something behind it.
+    fn accepts_hit(&self, time: f32) -> bool {
+        match self.first_hit {
+            Some(first) => time <= first,
+            None => false,
+        }
+    }
 }
 
 impl HasHitbox for Bullet {
     fn get_hitbox(&self) -> &Hitbox {
         &self.hitbox
     }
+
+    fn get_displacement(&self) -> Vector2 {
+        self.position - self.last_position
+    }
 }
 
 impl Object for Bullet {
@@ -99,6 +142,8 @@ impl Object for Bullet {
     }
 
     fn step(&mut self, dt: f32) {
+        self.last_position = self.position;
+        self.first_hit = None;
         self.lifetime += dt;
         self.physics.step(dt);
         self.update_position(dt);
@@ -1,46 +1,160 @@
-use ggez::graphics::Vector2;
+use std::cmp;
+use std::f32;
+use ggez::graphics::{Point2, Vector2};
 use super::HasHitbox;
 use super::HasPhysics;
 use super::HasCollisionEvents;
 use super::RecievesCollisionEvents;
 use super::Object;
-use utils::get_two;
+use super::broadphase::{SpatialHash, CELL_SIZE};
+use utils::toml_float;
 
+// One body's share of a collision: how far it has to move back out, and the change in velocity
+// from the bounce.
 #[derive(Clone, Copy)]
 pub struct Collision {
     penetration: Vector2,
+    velocity_change: Vector2,
 }
 
 impl Collision {
-    pub fn new(penetration: Vector2) -> Self {
-        Self { penetration }
+    pub fn new(penetration: Vector2, velocity_change: Vector2) -> Self {
+        Self {
+            penetration,
+            velocity_change,
+        }
     }
 
     pub fn get_penetration(&self) -> Vector2 {
         self.penetration
     }
+
+    pub fn get_velocity_change(&self) -> Vector2 {
+        self.velocity_change
+    }
+}
+
+// Where along a sweep two objects first touch. Time goes from 0 at the start of the step to 1 at
+// the end, and the normal is the unit vector out of the struck object's surface where it was hit.
+#[derive(Clone, Copy)]
+pub struct SweepHit {
+    time: f32,
+    normal: Vector2,
+}
+
+impl SweepHit {
+    pub fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn get_normal(&self) -> Vector2 {
+        self.normal
+    }
+}
+
+// The shape of an object, placed at the object's position. Every shape fills the box from the
+// position to the position plus its size, so a circle sits inside the square around it.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "HitboxDef", into = "HitboxDef")]
+pub enum Hitbox {
+    Aabb(Vector2),
+    Circle(f32),
+    // Corners of a convex polygon, counter-clockwise and relative to the position, with none of
+    // them below or left of it.
+    Polygon(Vec<Vector2>),
 }
 
-pub struct Hitbox(Vector2);
+//Stand-in for Hitbox when serializing, for the same reasons as EventDef.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+enum HitboxDef {
+    Aabb([f32; 2]),
+    Circle(f32),
+    Polygon(Vec<[f32; 2]>),
+}
+
+impl From<Hitbox> for HitboxDef {
+    fn from(hitbox: Hitbox) -> Self {
+        match hitbox {
+            Hitbox::Aabb(size) => HitboxDef::Aabb([toml_float(size.x), toml_float(size.y)]),
+            Hitbox::Circle(radius) => HitboxDef::Circle(radius),
+            Hitbox::Polygon(points) => HitboxDef::Polygon(
+                points
+                    .iter()
+                    .map(|point| [toml_float(point.x), toml_float(point.y)])
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<HitboxDef> for Hitbox {
+    fn from(hitbox: HitboxDef) -> Self {
+        match hitbox {
+            HitboxDef::Aabb(size) => Hitbox::Aabb(Vector2::new(size[0], size[1])),
+            HitboxDef::Circle(radius) => Hitbox::Circle(radius),
+            HitboxDef::Polygon(points) => Hitbox::Polygon(
+                points
+                    .iter()
+                    .map(|point| Vector2::new(point[0], point[1]))
+                    .collect(),
+            ),
+        }
+    }
+}
 
 impl Hitbox {
     pub fn new(size: Vector2) -> Self {
-        Hitbox(size)
+        Hitbox::Aabb(size)
+    }
+
+    pub fn circle(radius: f32) -> Self {
+        Hitbox::Circle(radius)
     }
 
+    pub fn polygon(points: Vec<Vector2>) -> Self {
+        Hitbox::Polygon(points)
+    }
+
+    // The size of the box around the shape.
     pub fn vec(&self) -> Vector2 {
-        self.0
+        match *self {
+            Hitbox::Aabb(size) => size,
+            Hitbox::Circle(radius) => Vector2::new(2.0 * radius, 2.0 * radius),
+            Hitbox::Polygon(ref points) => {
+                let mut size = Vector2::zeros();
+                for point in points {
+                    size.x = size.x.max(point.x);
+                    size.y = size.y.max(point.y);
+                }
+                size
+            }
+        }
+    }
+
+    // The corners of the shape counter-clockwise, relative to the position. None for circles.
+    pub fn get_points(&self) -> Option<Vec<Vector2>> {
+        match *self {
+            Hitbox::Aabb(size) => Some(vec![
+                Vector2::zeros(),
+                Vector2::new(size.x, 0.0),
+                size,
+                Vector2::new(0.0, size.y),
+            ]),
+            Hitbox::Circle(_) => None,
+            Hitbox::Polygon(ref points) => Some(points.clone()),
+        }
     }
 }
 
-fn get_hitbox_distances<T: HasHitbox, U: HasHitbox>(
-    object_1: &T,
-    object_2: &U,
+fn get_box_distances(
+    position_1: Point2,
+    size_1: Vector2,
+    position_2: Point2,
+    size_2: Vector2,
 ) -> (f32, f32, f32, f32) {
-    let (hitbox_1, hitbox_2) = (object_1.get_hitbox(), object_2.get_hitbox());
-    let (position_1, position_2) = (object_1.get_position(), object_2.get_position());
-    let h1 = position_1 + hitbox_1.vec();
-    let h2 = position_2 + hitbox_2.vec();
+    let h1 = position_1 + size_1;
+    let h2 = position_2 + size_2;
 
     //TODO move into vectors for more concise code.
     let dx1 = h2.x - position_1.x;
@@ -55,22 +169,293 @@ pub fn get_distance<T: Object, U: Object>(object_1: &T, object_2: &U) -> f32 {
     (object_1.get_position() - object_2.get_position()).norm()
 }
 
-pub fn is_intersecting<T: HasHitbox, U: HasHitbox>(object_1: &T, object_2: &U) -> bool {
-    let (dx1, dx2, dy1, dy2) = get_hitbox_distances(object_1, object_2);
-    (dx1 > 0.0) & (dx2 > 0.0) & (dy1 > 0.0) & (dy2 > 0.0)
+// The box that covers an object's hitbox over the whole of its last step.
+pub fn get_swept_bounds<T: HasHitbox>(object: &T) -> (Point2, Vector2) {
+    let displacement = object.get_displacement();
+    let end = object.get_position();
+    let start = end - displacement;
+    let min = Point2::new(start.x.min(end.x), start.y.min(end.y));
+    let size = object.get_hitbox().vec() + Vector2::new(displacement.x.abs(), displacement.y.abs());
+    (min, size)
 }
 
-pub fn find_penetration<T: HasHitbox, U: HasHitbox>(object_1: &T, object_2: &U) -> Vector2 {
+// Sweep object_2 over its last step, relative to object_1, and find where it first touches
+// object_1. Anything that was already overlapping at the start hits at time 0, with the normal
+// along the shortest way out. With neither object moving this is a plain overlap test.
+pub fn sweep<T: HasHitbox, U: HasHitbox>(object_1: &T, object_2: &U) -> Option<SweepHit> {
     let (hitbox_1, hitbox_2) = (object_1.get_hitbox(), object_2.get_hitbox());
-    let (position_1, position_2) = (object_1.get_position(), object_2.get_position());
-    let h1 = position_1 + hitbox_1.vec();
-    let h2 = position_2 + hitbox_2.vec();
+    let start_1 = object_1.get_position() - object_1.get_displacement();
+    let start_2 = object_2.get_position() - object_2.get_displacement();
+    let displacement = object_2.get_displacement() - object_1.get_displacement();
 
-    //TODO move into vectors for more concise code.
-    let dx1 = h2.x - position_1.x;
-    let dx2 = h1.x - position_2.x;
-    let dy1 = h2.y - position_1.y;
-    let dy2 = h1.y - position_2.y;
+    let penetration = get_penetration(start_1, hitbox_1, start_2, hitbox_2);
+    if penetration != Vector2::zeros() {
+        let normal = penetration.normalize();
+        return Some(SweepHit { time: 0.0, normal });
+    }
+
+    let hit = match (hitbox_1, hitbox_2) {
+        (&Hitbox::Aabb(size_1), &Hitbox::Aabb(size_2)) => {
+            //Treat object_2 as a point at its corner, and object_1 as grown by object_2's size.
+            ray_box(start_2, displacement, start_1 - size_2, start_1 + size_1)
+        }
+        (&Hitbox::Circle(radius_1), &Hitbox::Circle(radius_2)) => {
+            let center_1 = start_1 + Vector2::new(radius_1, radius_1);
+            let center_2 = start_2 + Vector2::new(radius_2, radius_2);
+            ray_circle(center_2, displacement, center_1, radius_1 + radius_2)
+        }
+        (_, &Hitbox::Circle(radius_2)) => {
+            let center_2 = start_2 + Vector2::new(radius_2, radius_2);
+            let points_1 = get_world_points(start_1, hitbox_1);
+            ray_rounded_polygon(center_2, displacement, &points_1, radius_2)
+        }
+        (&Hitbox::Circle(radius_1), _) => {
+            //Sweep the circle backwards over object_2 instead, and turn the normal around.
+            let center_1 = start_1 + Vector2::new(radius_1, radius_1);
+            let points_2 = get_world_points(start_2, hitbox_2);
+            ray_rounded_polygon(center_1, -1.0 * displacement, &points_2, radius_1)
+                .map(|(time, normal)| (time, -1.0 * normal))
+        }
+        _ => {
+            //Treat object_2 as a point at its position, and object_1 as grown by object_2's
+            //shape turned around.
+            let points_1 = get_world_points(start_1, hitbox_1);
+            let points_2 = hitbox_2.get_points().unwrap_or_default();
+            let mut grown = Vec::new();
+            for point_1 in &points_1 {
+                for point_2 in &points_2 {
+                    grown.push(point_1 - point_2);
+                }
+            }
+            ray_polygon(start_2, displacement, &convex_hull(grown))
+        }
+    };
+    hit.map(|(time, normal)| SweepHit { time, normal })
+}
+
+// Where a ray from origin along delta first enters the box from low to high, as the fraction of
+// delta travelled and the face it comes in through. Only hits within the length of delta count.
+pub fn ray_box(origin: Point2, delta: Vector2, low: Point2, high: Point2) -> Option<(f32, Vector2)> {
+    let mut entry = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    let mut normal = Vector2::zeros();
+    let axes = [
+        (origin.x, delta.x, low.x, high.x, Vector2::new(1.0, 0.0)),
+        (origin.y, delta.y, low.y, high.y, Vector2::new(0.0, 1.0)),
+    ];
+    for &(start, delta, low, high, axis) in axes.iter() {
+        if delta == 0.0 {
+            if start <= low || start >= high {
+                return None;
+            }
+            continue;
+        }
+        let (near, far, face) = if delta > 0.0 {
+            (low, high, -1.0 * axis)
+        } else {
+            (high, low, axis)
+        };
+        let axis_entry = (near - start) / delta;
+        let axis_exit = (far - start) / delta;
+        if axis_entry > entry {
+            entry = axis_entry;
+            normal = face;
+        }
+        exit = exit.min(axis_exit);
+    }
+
+    if entry < exit && entry >= 0.0 && entry <= 1.0 {
+        Some((entry, normal))
+    } else {
+        None
+    }
+}
+
+// Like ray_box, against a circle.
+pub fn ray_circle(
+    origin: Point2,
+    delta: Vector2,
+    center: Point2,
+    radius: f32,
+) -> Option<(f32, Vector2)> {
+    let offset = origin - center;
+    let a = delta.dot(&delta);
+    let b = 2.0 * offset.dot(&delta);
+    let c = offset.dot(&offset) - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if a == 0.0 || discriminant < 0.0 {
+        return None;
+    }
+    let time = (-1.0 * b - discriminant.sqrt()) / (2.0 * a);
+    if time >= 0.0 && time <= 1.0 {
+        let normal = (origin + time * delta - center) / radius;
+        Some((time, normal))
+    } else {
+        None
+    }
+}
+
+// Like ray_box, against a convex polygon with its corners counter-clockwise. The ray is clipped
+// against each edge in turn, the same as ray_box does with each pair of faces.
+pub fn ray_polygon(origin: Point2, delta: Vector2, points: &[Point2]) -> Option<(f32, Vector2)> {
+    let mut entry = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    let mut normal = Vector2::zeros();
+    for (i, point) in points.iter().enumerate() {
+        let face = get_edge_normal(*point, points[(i + 1) % points.len()]);
+        let distance = face.dot(&(origin - point));
+        let speed = face.dot(&delta);
+        if speed == 0.0 {
+            if distance >= 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let time = -1.0 * distance / speed;
+        if speed < 0.0 {
+            if time > entry {
+                entry = time;
+                normal = face;
+            }
+        } else {
+            exit = exit.min(time);
+        }
+    }
+
+    if entry < exit && entry >= 0.0 && entry <= 1.0 {
+        Some((entry, normal))
+    } else {
+        None
+    }
+}
+
+// Like ray_polygon, against the polygon grown by radius with rounded corners, which is where the
+// center of a circle touching the polygon can be. That shape is made of each edge pushed out into
+// a strip and a circle on each corner, so the first of those to be hit is the hit.
+fn ray_rounded_polygon(
+    origin: Point2,
+    delta: Vector2,
+    points: &[Point2],
+    radius: f32,
+) -> Option<(f32, Vector2)> {
+    let mut first: Option<(f32, Vector2)> = None;
+    for (i, point) in points.iter().enumerate() {
+        let next = points[(i + 1) % points.len()];
+        let grow = radius * get_edge_normal(*point, next);
+        let strip = [*point, point + grow, next + grow, next];
+        let hits = [
+            ray_polygon(origin, delta, &strip),
+            ray_circle(origin, delta, *point, radius),
+        ];
+        for hit in hits.iter() {
+            if let Some((time, normal)) = *hit {
+                first = match first {
+                    Some(first) if first.0 <= time => Some(first),
+                    _ => Some((time, normal)),
+                };
+            }
+        }
+    }
+    first
+}
+
+// The outward facing normal of the edge from point to next, going counter-clockwise.
+fn get_edge_normal(point: Point2, next: Point2) -> Vector2 {
+    let edge = next - point;
+    Vector2::new(edge.y, -1.0 * edge.x).normalize()
+}
+
+fn get_world_points(position: Point2, hitbox: &Hitbox) -> Vec<Point2> {
+    hitbox
+        .get_points()
+        .unwrap_or_default()
+        .iter()
+        .map(|point| position + point)
+        .collect()
+}
+
+// The smallest convex polygon around the points, counter-clockwise.
+pub fn convex_hull(mut points: Vec<Point2>) -> Vec<Point2> {
+    points.sort_by(|a, b| {
+        (a.x, a.y)
+            .partial_cmp(&(b.x, b.y))
+            .unwrap_or(cmp::Ordering::Equal)
+    });
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    //Build the lower half going right and the upper half coming back, dropping any point that
+    //doesn't turn left.
+    let mut hull: Vec<Point2> = Vec::new();
+    for pass in 0..2 {
+        let start = hull.len();
+        for i in 0..points.len() {
+            let point = if pass == 0 {
+                points[i]
+            } else {
+                points[points.len() - 1 - i]
+            };
+            while hull.len() >= start + 2 {
+                let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+                if cross(b - a, point - b) > 0.0 {
+                    break;
+                }
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        //The last point is the first of the other half.
+        hull.pop();
+    }
+    hull
+}
+
+pub fn cross(a: Vector2, b: Vector2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+// How far object_1 has to move back to get out of object_2, or zero if they don't overlap.
+pub fn find_penetration<T: HasHitbox, U: HasHitbox>(object_1: &T, object_2: &U) -> Vector2 {
+    get_penetration(
+        object_1.get_position(),
+        object_1.get_hitbox(),
+        object_2.get_position(),
+        object_2.get_hitbox(),
+    )
+}
+
+fn get_penetration(
+    position_1: Point2,
+    hitbox_1: &Hitbox,
+    position_2: Point2,
+    hitbox_2: &Hitbox,
+) -> Vector2 {
+    match (hitbox_1, hitbox_2) {
+        (&Hitbox::Aabb(size_1), &Hitbox::Aabb(size_2)) => {
+            box_box_penetration(position_1, size_1, position_2, size_2)
+        }
+        (&Hitbox::Circle(radius_1), &Hitbox::Aabb(size_2)) => {
+            circle_box_penetration(position_1, radius_1, position_2, size_2)
+        }
+        (&Hitbox::Aabb(size_1), &Hitbox::Circle(radius_2)) => {
+            -1.0 * circle_box_penetration(position_2, radius_2, position_1, size_1)
+        }
+        (&Hitbox::Circle(radius_1), &Hitbox::Circle(radius_2)) => {
+            circle_circle_penetration(position_1, radius_1, position_2, radius_2)
+        }
+        _ => sat_penetration(position_1, hitbox_1, position_2, hitbox_2),
+    }
+}
+
+fn box_box_penetration(
+    position_1: Point2,
+    size_1: Vector2,
+    position_2: Point2,
+    size_2: Vector2,
+) -> Vector2 {
+    let (dx1, dx2, dy1, dy2) = get_box_distances(position_1, size_1, position_2, size_2);
 
     if (dx1 > 0.0) & (dx2 > 0.0) & (dy1 > 0.0) & (dy2 > 0.0) {
         let px = match dx1.abs() < dx2.abs() {
@@ -90,8 +475,222 @@ pub fn find_penetration<T: HasHitbox, U: HasHitbox>(object_1: &T, object_2: &U)
     }
 }
 
-pub fn create_collision<T: HasPhysics, U: HasPhysics>(object_1: &T, object_2: &U) -> Collision {
-    Collision::new(find_penetration(object_1, object_2))
+fn circle_box_penetration(
+    position_1: Point2,
+    radius: f32,
+    position_2: Point2,
+    size: Vector2,
+) -> Vector2 {
+    let center = position_1 + Vector2::new(radius, radius);
+    let (low, high) = (position_2, position_2 + size);
+    let closest = Point2::new(center.x.max(low.x).min(high.x), center.y.max(low.y).min(high.y));
+    let offset = center - closest;
+    let distance = offset.norm();
+
+    if distance > 0.0 {
+        if distance < radius {
+            -1.0 * (radius - distance) * offset / distance
+        } else {
+            Vector2::zeros()
+        }
+    } else {
+        //The center is inside the box, so get out through the nearest face.
+        let exits = [
+            (center.x - low.x, Vector2::new(1.0, 0.0)),
+            (high.x - center.x, Vector2::new(-1.0, 0.0)),
+            (center.y - low.y, Vector2::new(0.0, 1.0)),
+            (high.y - center.y, Vector2::new(0.0, -1.0)),
+        ];
+        let mut closest = exits[0];
+        for &exit in exits.iter() {
+            if exit.0 < closest.0 {
+                closest = exit;
+            }
+        }
+        (closest.0 + radius) * closest.1
+    }
+}
+
+fn circle_circle_penetration(
+    position_1: Point2,
+    radius_1: f32,
+    position_2: Point2,
+    radius_2: f32,
+) -> Vector2 {
+    let center_1 = position_1 + Vector2::new(radius_1, radius_1);
+    let center_2 = position_2 + Vector2::new(radius_2, radius_2);
+    let offset = center_2 - center_1;
+    let distance = offset.norm();
+    let overlap = radius_1 + radius_2 - distance;
+
+    if overlap <= 0.0 {
+        Vector2::zeros()
+    } else if distance > 0.0 {
+        overlap * offset / distance
+    } else {
+        //Exactly on top of each other, so any way out will do.
+        Vector2::new(0.0, overlap)
+    }
+}
+
+// Separating axis test for anything involving a polygon. Two convex shapes overlap only if their
+// shadows overlap along every edge normal of both, plus, for a circle, the line from its center to
+// the nearest corner of the other shape. The axis with the smallest overlap is the shortest way
+// out.
+fn sat_penetration(
+    position_1: Point2,
+    hitbox_1: &Hitbox,
+    position_2: Point2,
+    hitbox_2: &Hitbox,
+) -> Vector2 {
+    let points_1 = get_world_points(position_1, hitbox_1);
+    let points_2 = get_world_points(position_2, hitbox_2);
+    let mut axes = Vec::new();
+    for points in [&points_1, &points_2].iter() {
+        for (i, point) in points.iter().enumerate() {
+            axes.push(get_edge_normal(*point, points[(i + 1) % points.len()]));
+        }
+    }
+    for &(position, hitbox, others) in [
+        (position_1, hitbox_1, &points_2),
+        (position_2, hitbox_2, &points_1),
+    ].iter()
+    {
+        if let Hitbox::Circle(radius) = *hitbox {
+            let center = position + Vector2::new(radius, radius);
+            let mut nearest: Option<Vector2> = None;
+            for point in others.iter() {
+                let offset = center - point;
+                nearest = match nearest {
+                    Some(nearest) if nearest.norm() <= offset.norm() => Some(nearest),
+                    _ => Some(offset),
+                };
+            }
+            if let Some(axis) = nearest.and_then(|offset| offset.try_normalize(0.0)) {
+                axes.push(axis);
+            }
+        }
+    }
+
+    let mut penetration: Option<Vector2> = None;
+    for axis in axes {
+        let (min_1, max_1) = project(position_1, hitbox_1, &points_1, axis);
+        let (min_2, max_2) = project(position_2, hitbox_2, &points_2, axis);
+        //How far object_1 would have to move back if object_2 is ahead of it along the axis, and
+        //how far forward if object_2 is behind.
+        let ahead = max_1 - min_2;
+        let behind = max_2 - min_1;
+        if ahead <= 0.0 || behind <= 0.0 {
+            return Vector2::zeros();
+        }
+        let candidate = if ahead < behind {
+            ahead * axis
+        } else {
+            -1.0 * behind * axis
+        };
+        penetration = match penetration {
+            Some(shortest) if shortest.norm() <= candidate.norm() => Some(shortest),
+            _ => Some(candidate),
+        };
+    }
+    penetration.unwrap_or_else(Vector2::zeros)
+}
+
+// The shadow of a shape along an axis, given its corners in the world.
+fn project(position: Point2, hitbox: &Hitbox, points: &[Point2], axis: Vector2) -> (f32, f32) {
+    if let Hitbox::Circle(radius) = *hitbox {
+        let center = (position + Vector2::new(radius, radius)).coords.dot(&axis);
+        return (center - radius, center + radius);
+    }
+    let mut min = f32::INFINITY;
+    let mut max = f32::NEG_INFINITY;
+    for point in points {
+        let distance = point.coords.dot(&axis);
+        min = min.min(distance);
+        max = max.max(distance);
+    }
+    (min, max)
+}
+
+// Split a collision between two bodies. Each is pushed out in proportion to its inverse mass, so
+// the lighter body moves further and a body with no inverse mass, like a wall, doesn't move at
+// all. The bounce is an impulse along the contact normal, and the bouncier of the two bodies
+// decides how much speed it keeps, so a rubber wall bounces anything off it and a bouncy mob
+// bounces off anything.
+pub fn create_collisions<T: HasPhysics, U: HasPhysics>(
+    object_1: &T,
+    object_2: &U,
+) -> Option<(Collision, Collision)> {
+    let penetration = find_penetration(object_1, object_2);
+    let (inverse_mass_1, inverse_mass_2) = (object_1.get_inverse_mass(), object_2.get_inverse_mass());
+    let total_inverse_mass = inverse_mass_1 + inverse_mass_2;
+    if penetration == Vector2::zeros() || total_inverse_mass <= 0.0 {
+        return None;
+    }
+
+    //Points from object_2 towards object_1.
+    let normal = -1.0 * penetration.normalize();
+    let elasticity = object_1.get_elasticity().max(object_2.get_elasticity());
+    let approach = (object_1.get_velocity() - object_2.get_velocity()).dot(&normal);
+    let impulse = if approach < 0.0 {
+        -1.0 * (1.0 + elasticity) * approach / total_inverse_mass
+    } else {
+        0.0
+    };
+
+    let share_1 = inverse_mass_1 / total_inverse_mass;
+    let share_2 = inverse_mass_2 / total_inverse_mass;
+    Some((
+        Collision::new(share_1 * penetration, impulse * inverse_mass_1 * normal),
+        Collision::new(-1.0 * share_2 * penetration, -1.0 * impulse * inverse_mass_2 * normal),
+    ))
+}
+
+// Returns whether the bodies were touching.
+pub fn resolve_collision<T: HasPhysics, U: HasPhysics>(
+    dt: f32,
+    object_1: &mut T,
+    object_2: &mut U,
+) -> bool {
+    match create_collisions(object_1, object_2) {
+        Some((collision_1, collision_2)) => {
+            object_1.recieve_collision(dt, collision_1);
+            object_2.recieve_collision(dt, collision_2);
+            true
+        }
+        None => false,
+    }
+}
+
+// Resolve object_1 against the objects in the grid from index `first` on, where list holds those
+// objects starting at `first`. Pairs are visited in index order, and anything object_1 doesn't
+// share a cell with can't be touching it. Once a pair has been pushed apart both may be in new
+// cells, so the grid is updated and the candidates are looked up again.
+fn grid_physics<T: HasPhysics, U: HasPhysics>(
+    dt: f32,
+    object_1: &mut T,
+    list: &mut [U],
+    first: usize,
+    grid: &mut SpatialHash,
+) {
+    let mut candidates = grid.query_object(object_1);
+    let mut i = 0;
+    while i < candidates.len() {
+        let j = candidates[i];
+        i += 1;
+        if j < first {
+            continue;
+        }
+        let object_2 = &mut list[j - first];
+        if resolve_collision(dt, object_1, object_2) {
+            grid.insert_object(j, object_2);
+            candidates = grid.query_object(object_1)
+                .into_iter()
+                .filter(|k| *k > j)
+                .collect();
+            i = 0;
+        }
+    }
 }
 
 pub fn object_vec_physics<T: HasPhysics, U: HasPhysics>(
@@ -99,10 +698,8 @@ pub fn object_vec_physics<T: HasPhysics, U: HasPhysics>(
     object_1: &mut T,
     list: &mut Vec<U>,
 ) {
-    for object_2 in list.iter_mut() {
-        let collision = create_collision(object_1, object_2);
-        object_1.recieve_collision(dt, collision);
-    }
+    let mut grid = SpatialHash::from_list(CELL_SIZE, list);
+    grid_physics(dt, object_1, list, 0, &mut grid);
 }
 
 pub fn vec_vec_physics<T: HasPhysics, U: HasPhysics>(
@@ -110,23 +707,483 @@ pub fn vec_vec_physics<T: HasPhysics, U: HasPhysics>(
     objects_1: &mut Vec<T>,
     objects_2: &mut Vec<U>,
 ) {
+    let mut grid = SpatialHash::from_list(CELL_SIZE, objects_2);
     for object_1 in objects_1.iter_mut() {
-        for object_2 in objects_2.iter_mut() {
-            let collision = create_collision(object_1, object_2);
-            object_1.recieve_collision(dt, collision);
-        }
+        grid_physics(dt, object_1, objects_2, 0, &mut grid);
     }
 }
 
-
+// Every pair is resolved once, by the lower index of the two.
 pub fn vec_physics<T: HasPhysics>(dt: f32, list: &mut Vec<T>) {
-    let n = list.len();
-    for x in 0..n {
-        for y in 0..n {
-            if let Ok((object_1, object_2)) = get_two(list, x, y) {
-                let collision = create_collision(object_1, object_2);
-                object_1.recieve_collision(dt, collision);
-            }
+    let mut grid = SpatialHash::from_list(CELL_SIZE, list);
+    for x in 0..list.len() {
+        let (object_1, rest) = list[x..].split_first_mut().unwrap();
+        grid_physics(dt, object_1, rest, x + 1, &mut grid);
+        grid.insert_object(x, object_1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ggez::graphics::{Color, Point2, Vector2};
+    use game_object::*;
+    use game_object::block::Block;
+    use game_object::bullet::Bullet;
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    // A bare hitbox that moved by displacement to get to position.
+    struct Shape {
+        position: Point2,
+        displacement: Vector2,
+        hitbox: Hitbox,
+    }
+
+    impl Object for Shape {
+        fn get_position(&self) -> Point2 {
+            self.position
+        }
+    }
+
+    impl HasHitbox for Shape {
+        fn get_hitbox(&self) -> &Hitbox {
+            &self.hitbox
+        }
+
+        fn get_displacement(&self) -> Vector2 {
+            self.displacement
+        }
+    }
+
+    // A 10 by 10 box that moves and can be pushed about.
+    struct Body {
+        position: Point2,
+        hitbox: Hitbox,
+        velocity: Vector2,
+        mass: f32,
+        elasticity: f32,
+    }
+
+    impl Object for Body {
+        fn get_position(&self) -> Point2 {
+            self.position
         }
     }
+
+    impl HasHitbox for Body {
+        fn get_hitbox(&self) -> &Hitbox {
+            &self.hitbox
+        }
+    }
+
+    impl HasPhysics for Body {
+        fn get_elasticity(&self) -> f32 {
+            self.elasticity
+        }
+
+        fn get_velocity(&self) -> Vector2 {
+            self.velocity
+        }
+
+        fn get_inverse_mass(&self) -> f32 {
+            1.0 / self.mass
+        }
+    }
+
+    fn body(x: f32, velocity: Vector2) -> Body {
+        Body {
+            position: Point2::new(x, 0.0),
+            hitbox: Hitbox::new(Vector2::new(10.0, 10.0)),
+            velocity,
+            mass: 1.0,
+            elasticity: 0.0,
+        }
+    }
+
+    fn shape(x: f32, y: f32, hitbox: Hitbox) -> Shape {
+        moving(x, y, 0.0, 0.0, hitbox)
+    }
+
+    // A shape that starts at x, y and moves by dx, dy.
+    fn moving(x: f32, y: f32, dx: f32, dy: f32, hitbox: Hitbox) -> Shape {
+        Shape {
+            position: Point2::new(x + dx, y + dy),
+            displacement: Vector2::new(dx, dy),
+            hitbox,
+        }
+    }
+
+    // A square of side 10 * sqrt(2) turned 45 degrees, with its corners on the middles of the
+    // sides of the box from 0, 0 to 20, 20.
+    fn diamond() -> Block {
+        let points = [
+            Point2::new(10.0, 0.0),
+            Point2::new(0.0, 10.0),
+            Point2::new(10.0, 20.0),
+            Point2::new(20.0, 10.0),
+        ];
+        Block::polygon(0, &points)
+    }
+
+    fn assert_near(actual: Vector2, expected: Vector2) {
+        assert!(
+            (actual - expected).norm() < EPSILON,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn assert_hit(hit: Option<(f32, Vector2)>, time: f32, normal: Vector2) {
+        let (hit_time, hit_normal) = hit.expect("expected a hit");
+        assert!((hit_time - time).abs() < EPSILON, "time {} != {}", hit_time, time);
+        assert!(
+            (hit_normal - normal).norm() < EPSILON,
+            "normal {:?} != {:?}",
+            hit_normal,
+            normal
+        );
+    }
+
+    fn assert_sweep(hit: Option<SweepHit>, time: f32, normal: Vector2) {
+        assert_hit(hit.map(|hit| (hit.get_time(), hit.get_normal())), time, normal);
+    }
+
+    fn square(low: f32, high: f32) -> Vec<Point2> {
+        vec![
+            Point2::new(low, low),
+            Point2::new(high, low),
+            Point2::new(high, high),
+            Point2::new(low, high),
+        ]
+    }
+
+    #[test]
+    fn ray_box_hits_the_near_face() {
+        let (low, high) = (Point2::new(0.0, 0.0), Point2::new(10.0, 10.0));
+        let hit = ray_box(Point2::new(-10.0, 5.0), Vector2::new(20.0, 0.0), low, high);
+        assert_hit(hit, 0.5, Vector2::new(-1.0, 0.0));
+        let hit = ray_box(Point2::new(5.0, 30.0), Vector2::new(0.0, -40.0), low, high);
+        assert_hit(hit, 0.5, Vector2::new(0.0, 1.0));
+        //Coming in at an angle through the top.
+        let hit = ray_box(Point2::new(0.0, -10.0), Vector2::new(10.0, 20.0), low, high);
+        assert_hit(hit, 0.5, Vector2::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn ray_box_misses() {
+        let (low, high) = (Point2::new(0.0, 0.0), Point2::new(10.0, 10.0));
+        //Passing by to the side.
+        assert!(ray_box(Point2::new(-10.0, 15.0), Vector2::new(20.0, 0.0), low, high).is_none());
+        //Stopping short.
+        assert!(ray_box(Point2::new(-10.0, 5.0), Vector2::new(5.0, 0.0), low, high).is_none());
+        //Moving away.
+        assert!(ray_box(Point2::new(-10.0, 5.0), Vector2::new(-20.0, 0.0), low, high).is_none());
+        //Starting inside isn't an entry.
+        assert!(ray_box(Point2::new(5.0, 5.0), Vector2::new(20.0, 0.0), low, high).is_none());
+    }
+
+    #[test]
+    fn ray_circle_hits_the_near_side() {
+        let (center, delta) = (Point2::new(0.0, 0.0), Vector2::new(20.0, 0.0));
+        let hit = ray_circle(Point2::new(-10.0, 0.0), delta, center, 5.0);
+        assert_hit(hit, 0.25, Vector2::new(-1.0, 0.0));
+        //Grazing at an angle, where the normal isn't the direction of travel.
+        let hit = ray_circle(Point2::new(-10.0, 3.0), delta, center, 5.0);
+        assert_hit(hit, 0.3, Vector2::new(-0.8, 0.6));
+        assert!(ray_circle(Point2::new(-10.0, 6.0), delta, center, 5.0).is_none());
+        assert!(ray_circle(Point2::new(-10.0, 0.0), 0.2 * delta, center, 5.0).is_none());
+    }
+
+    #[test]
+    fn ray_rounded_polygon_hits_edges_and_corners() {
+        let points = square(0.0, 10.0);
+        //Square on to an edge, the rounding pushes the edge out by the radius.
+        let delta = Vector2::new(20.0, 0.0);
+        let hit = ray_rounded_polygon(Point2::new(-10.0, 5.0), delta, &points, 2.0);
+        assert_hit(hit, 0.4, Vector2::new(-1.0, 0.0));
+        //Straight at a corner, it's hit on the rounding.
+        let hit = ray_rounded_polygon(
+            Point2::new(-10.0, -10.0),
+            Vector2::new(20.0, 20.0),
+            &points,
+            2.0,
+        );
+        let time = (10.0 - 2.0_f32.sqrt()) / 20.0;
+        assert_hit(hit, time, Vector2::new(-1.0, -1.0).normalize());
+        //Cutting past the corner, where a square grown by the radius would still be hit.
+        let hit = ray_rounded_polygon(
+            Point2::new(-6.75, 3.25),
+            Vector2::new(10.0, -10.0),
+            &points,
+            2.0,
+        );
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn sweep_finds_the_time_of_impact() {
+        let block = Block::with_size(0.0, 0.0, 10.0, 10.0);
+        let mut bullet = Bullet::new(
+            Point2::new(-21.0, 4.0),
+            Vector2::new(1200.0, 0.0),
+            Color::new(1.0, 1.0, 1.0, 1.0),
+            Vec::new(),
+            Vec::new(),
+        );
+        bullet.step(1.0 / 60.0);
+        //The bullet's edge starts 19 away and it moves 20.
+        assert_sweep(sweep(&block, &bullet), 0.95, Vector2::new(-1.0, 0.0));
+        //Already overlapping at the start is a hit at time 0.
+        bullet.step(1.0 / 60.0);
+        assert_sweep(sweep(&block, &bullet), 0.0, Vector2::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn box_sweeps_against_a_box() {
+        //Falling onto the top of the block, with its bottom 16 above it and moving 30.
+        let block = Block::with_size(0.0, 0.0, 10.0, 10.0);
+        let box_ = moving(2.0, -20.0, 0.0, 30.0, Hitbox::new(Vector2::new(4.0, 4.0)));
+        assert_sweep(sweep(&block, &box_), 16.0 / 30.0, Vector2::new(0.0, -1.0));
+        //Both moving, so only how they move relative to each other counts.
+        let moving_block = moving(-10.0, 0.0, 10.0, 0.0, Hitbox::new(Vector2::new(10.0, 10.0)));
+        let box_ = moving(-8.0, -20.0, 10.0, 30.0, Hitbox::new(Vector2::new(4.0, 4.0)));
+        assert_sweep(sweep(&moving_block, &box_), 16.0 / 30.0, Vector2::new(0.0, -1.0));
+        //Sliding past the side without touching.
+        let box_ = moving(-5.0, -20.0, 0.0, 30.0, Hitbox::new(Vector2::new(4.0, 4.0)));
+        assert!(sweep(&block, &box_).is_none());
+    }
+
+    #[test]
+    fn circle_sweeps_against_a_circle() {
+        //Centers 5 apart when they touch, which happens 4 to the left and 3 below, after the
+        //moving circle has gone 6 of its 20.
+        let still = shape(0.0, 0.0, Hitbox::circle(3.0));
+        let circle = moving(-9.0, 4.0, 20.0, 0.0, Hitbox::circle(2.0));
+        assert_sweep(sweep(&still, &circle), 0.3, Vector2::new(-0.8, 0.6));
+        let circle = moving(-9.0, 6.5, 20.0, 0.0, Hitbox::circle(2.0));
+        assert!(sweep(&still, &circle).is_none());
+    }
+
+    #[test]
+    fn fast_bullet_does_not_tunnel_through_a_thin_block() {
+        let block = Block::with_size(0.0, 0.0, 2.0, 100.0);
+        let mut bullet = Bullet::new(
+            Point2::new(-20.0, 50.0),
+            Vector2::new(3000.0, 0.0),
+            Color::new(1.0, 1.0, 1.0, 1.0),
+            Vec::new(),
+            Vec::new(),
+        );
+        bullet.step(1.0 / 60.0);
+        //The whole step goes from one side of the block to the other, and it ends up clear of it.
+        assert_eq!(find_penetration(&block, &bullet), Vector2::zeros());
+        //Its edge is 18 from the block at the start and it moves 50.
+        assert_sweep(sweep(&block, &bullet), 0.36, Vector2::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn convex_hull_keeps_only_the_corners() {
+        let points = vec![
+            Point2::new(5.0, 5.0),
+            Point2::new(10.0, 10.0),
+            Point2::new(0.0, 0.0),
+            Point2::new(5.0, 0.0),
+            Point2::new(0.0, 10.0),
+            Point2::new(10.0, 0.0),
+            Point2::new(10.0, 10.0),
+            Point2::new(2.0, 8.0),
+        ];
+        let hull = convex_hull(points);
+        assert_eq!(hull, square(0.0, 10.0));
+        //Counter-clockwise, so every edge normal faces out.
+        for (i, point) in hull.iter().enumerate() {
+            let next = hull[(i + 1) % hull.len()];
+            let after = hull[(i + 2) % hull.len()];
+            assert!(cross(next - point, after - next) > 0.0);
+        }
+        //Too few points to make a polygon come back as they are.
+        let line = vec![Point2::new(3.0, 0.0), Point2::new(0.0, 0.0), Point2::new(3.0, 0.0)];
+        assert_eq!(convex_hull(line), vec![Point2::new(0.0, 0.0), Point2::new(3.0, 0.0)]);
+    }
+
+    #[test]
+    fn polygon_polygon_penetration() {
+        //The diamond's right corner is 2 into the left of the square.
+        let points = square(0.0, 10.0)
+            .iter()
+            .map(|point| point + Vector2::new(18.0, 5.0))
+            .collect::<Vec<_>>();
+        let square = Block::polygon(1, &points);
+        assert_near(find_penetration(&diamond(), &square), Vector2::new(2.0, 0.0));
+        assert_near(find_penetration(&square, &diamond()), Vector2::new(-2.0, 0.0));
+        let apart = shape(21.0, 5.0, Hitbox::polygon(square.get_hitbox().get_points().unwrap()));
+        assert_eq!(find_penetration(&diamond(), &apart), Vector2::zeros());
+    }
+
+    #[test]
+    fn polygon_box_penetration() {
+        //The box's corner is sqrt(2) into the diamond's upper right face, so the way out is along
+        //that face's normal rather than either axis of the box.
+        let block = Block::with_size(14.0, 14.0, 10.0, 10.0);
+        assert_near(find_penetration(&diamond(), &block), Vector2::new(1.0, 1.0));
+        assert_near(find_penetration(&block, &diamond()), Vector2::new(-1.0, -1.0));
+        //Inside the diamond's bounding box but clear of the diamond.
+        let corner = Block::with_size(15.5, 15.5, 10.0, 10.0);
+        assert_eq!(find_penetration(&diamond(), &corner), Vector2::zeros());
+    }
+
+    #[test]
+    fn polygon_circle_penetration() {
+        //Centered on 17, 17, facing the upper right face 4 / sqrt(2) from it.
+        let circle = shape(12.0, 12.0, Hitbox::circle(5.0));
+        let depth = 5.0 - 4.0 / 2.0_f32.sqrt();
+        let normal = Vector2::new(1.0, 1.0).normalize();
+        assert_near(find_penetration(&diamond(), &circle), depth * normal);
+        assert_near(find_penetration(&circle, &diamond()), -1.0 * depth * normal);
+        //Past the right corner, where the way out is straight away from the corner.
+        let circle = shape(18.0, 5.0, Hitbox::circle(5.0));
+        assert_near(find_penetration(&diamond(), &circle), Vector2::new(2.0, 0.0));
+        //Off the corner, near enough to hit a box but not the diamond.
+        let circle = shape(-2.0, -2.0, Hitbox::circle(4.0));
+        assert_eq!(find_penetration(&diamond(), &circle), Vector2::zeros());
+    }
+
+    #[test]
+    fn box_sweeps_against_a_rotated_block() {
+        //Moving right into the left corner. The box's right side starts 6 away and moves 20.
+        let box_ = moving(-10.0, 8.0, 20.0, 0.0, Hitbox::new(Vector2::new(4.0, 4.0)));
+        assert_sweep(sweep(&diamond(), &box_), 0.3, Vector2::new(-1.0, 0.0));
+        //Moving diagonally into the upper left face. The box's corner starts at x + y = -12 and
+        //the face is at x + y = 10.
+        let box_ = moving(-10.0, -10.0, 20.0, 20.0, Hitbox::new(Vector2::new(4.0, 4.0)));
+        let face = Vector2::new(-1.0, -1.0).normalize();
+        assert_sweep(sweep(&diamond(), &box_), 0.55, face);
+        //The same, with the diamond moving into a still box, which it hits on the box's corner.
+        let block = Block::with_size(-10.0, -10.0, 4.0, 4.0);
+        let moving_diamond = moving(0.0, 0.0, -20.0, -20.0, diamond().get_hitbox().clone());
+        assert_sweep(sweep(&block, &moving_diamond), 0.55, -1.0 * face);
+    }
+
+    #[test]
+    fn circle_sweeps_against_a_rotated_block() {
+        //The center moves diagonally from x + y = -10 towards the upper left face at x + y = 10,
+        //stopping the radius short of it.
+        let circle = moving(-7.0, -7.0, 20.0, 20.0, Hitbox::circle(2.0));
+        let time = (20.0 - 2.0 * 2.0_f32.sqrt()) / 40.0;
+        let face = Vector2::new(-1.0, -1.0).normalize();
+        assert_sweep(sweep(&diamond(), &circle), time, face);
+        //The same, with the diamond moving into a still circle. The normal is out of the circle.
+        let circle = shape(-7.0, -7.0, Hitbox::circle(2.0));
+        let moving_diamond = moving(0.0, 0.0, -20.0, -20.0, diamond().get_hitbox().clone());
+        assert_sweep(sweep(&circle, &moving_diamond), time, -1.0 * face);
+        //Cutting across the corner of the diamond's bounding box, parallel to the face.
+        let circle = moving(-7.0, 6.0, 13.0, -13.0, Hitbox::circle(2.0));
+        assert!(sweep(&diamond(), &circle).is_none());
+        assert!(sweep(&Block::with_size(0.0, 0.0, 20.0, 20.0), &circle).is_some());
+    }
+
+    #[test]
+    fn bounce_keeps_the_tangential_velocity() {
+        //Moving right and down into a wall 2 deep, so only the rightwards part is bounced.
+        let mut wall = Block::with_size(8.0, -50.0, 10.0, 100.0);
+        let body = body(0.0, Vector2::new(10.0, 5.0));
+        let (collision, _) = create_collisions(&body, &wall).unwrap();
+        assert_near(body.velocity + collision.get_velocity_change(), Vector2::new(0.0, 5.0));
+        //The bouncier of the two decides, so an elastic wall bounces an inelastic body.
+        wall = wall.with_elasticity(1.0);
+        let (collision, _) = create_collisions(&body, &wall).unwrap();
+        assert_near(body.velocity + collision.get_velocity_change(), Vector2::new(-10.0, 5.0));
+    }
+
+    #[test]
+    fn bounce_between_two_bodies() {
+        let mut body_1 = body(0.0, Vector2::new(10.0, 0.0));
+        let mut body_2 = body(8.0, Vector2::new(-10.0, 0.0));
+        //Inelastic, so they end up moving together, and momentum is kept.
+        let (collision_1, collision_2) = create_collisions(&body_1, &body_2).unwrap();
+        assert_near(body_1.velocity + collision_1.get_velocity_change(), Vector2::zeros());
+        assert_near(body_2.velocity + collision_2.get_velocity_change(), Vector2::zeros());
+        //Half elastic, so they come apart at half the speed they met at.
+        body_2.elasticity = 0.5;
+        let (collision_1, collision_2) = create_collisions(&body_1, &body_2).unwrap();
+        assert_near(body_1.velocity + collision_1.get_velocity_change(), Vector2::new(-5.0, 0.0));
+        assert_near(body_2.velocity + collision_2.get_velocity_change(), Vector2::new(5.0, 0.0));
+        //Already moving apart, so they're pushed out of each other without a bounce.
+        body_1.velocity = Vector2::new(-10.0, 0.0);
+        let (collision_1, collision_2) = create_collisions(&body_1, &body_2).unwrap();
+        assert_eq!(collision_1.get_velocity_change(), Vector2::zeros());
+        assert_eq!(collision_2.get_velocity_change(), Vector2::zeros());
+        assert_near(collision_1.get_penetration(), Vector2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn push_out_is_split_by_inverse_mass() {
+        //2 deep. Equal masses move back the same distance.
+        let mut body_1 = body(0.0, Vector2::zeros());
+        let body_2 = body(8.0, Vector2::zeros());
+        let (collision_1, collision_2) = create_collisions(&body_1, &body_2).unwrap();
+        assert_near(collision_1.get_penetration(), Vector2::new(1.0, 0.0));
+        assert_near(collision_2.get_penetration(), Vector2::new(-1.0, 0.0));
+        //Three times as heavy moves a third as far.
+        body_1.mass = 3.0;
+        let (collision_1, collision_2) = create_collisions(&body_1, &body_2).unwrap();
+        assert_near(collision_1.get_penetration(), Vector2::new(0.5, 0.0));
+        assert_near(collision_2.get_penetration(), Vector2::new(-1.5, 0.0));
+    }
+
+    #[test]
+    fn blocks_are_not_pushed() {
+        let block = Block::with_size(8.0, 0.0, 10.0, 10.0);
+        let body = body(0.0, Vector2::new(10.0, 0.0));
+        let (collision_1, collision_2) = create_collisions(&body, &block).unwrap();
+        assert_near(collision_1.get_penetration(), Vector2::new(2.0, 0.0));
+        assert_eq!(collision_2.get_penetration(), Vector2::zeros());
+        assert_eq!(collision_2.get_velocity_change(), Vector2::zeros());
+        //The same the other way round.
+        let (collision_1, collision_2) = create_collisions(&block, &body).unwrap();
+        assert_eq!(collision_1.get_penetration(), Vector2::zeros());
+        assert_near(collision_2.get_penetration(), Vector2::new(2.0, 0.0));
+        //Two blocks can't push each other at all.
+        let other = Block::with_size(0.0, 0.0, 10.0, 10.0);
+        assert!(create_collisions(&other, &block).is_none());
+    }
+
+    #[test]
+    fn circle_box_penetration_at_faces_and_corners() {
+        let block = Block::with_size(0.0, 0.0, 10.0, 10.0);
+        //Centered 3 off the right face.
+        let circle = shape(8.0, 0.0, Hitbox::circle(5.0));
+        assert_near(find_penetration(&circle, &block), Vector2::new(-2.0, 0.0));
+        assert_near(find_penetration(&block, &circle), Vector2::new(2.0, 0.0));
+        //Centered 4 off the bottom right corner, along a 3-4-5 triangle.
+        let circle = shape(7.4, 8.2, Hitbox::circle(5.0));
+        assert_near(find_penetration(&circle, &block), Vector2::new(-0.6, -0.8));
+        assert_near(find_penetration(&block, &circle), Vector2::new(0.6, 0.8));
+        //Off the corner, where the square around the circle would still overlap.
+        let circle = shape(9.0, 9.0, Hitbox::circle(5.0));
+        assert_eq!(find_penetration(&circle, &block), Vector2::zeros());
+        //Centered inside, 1 from the left face, so out that way with the radius on top.
+        let circle = shape(-1.0, 3.0, Hitbox::circle(2.0));
+        assert_near(find_penetration(&circle, &block), Vector2::new(3.0, 0.0));
+    }
+
+    #[test]
+    fn circle_circle_penetration_along_the_centers() {
+        let circle_1 = shape(0.0, 0.0, Hitbox::circle(3.0));
+        //Centers 4 apart, side by side.
+        let circle_2 = shape(5.0, 1.0, Hitbox::circle(2.0));
+        assert_near(find_penetration(&circle_1, &circle_2), Vector2::new(1.0, 0.0));
+        assert_near(find_penetration(&circle_2, &circle_1), Vector2::new(-1.0, 0.0));
+        //Centers 4 apart, on a diagonal.
+        let circle_2 = shape(1.0 + 2.4, 1.0 + 3.2, Hitbox::circle(2.0));
+        assert_near(find_penetration(&circle_1, &circle_2), Vector2::new(0.6, 0.8));
+        //Just out of reach.
+        let circle_2 = shape(6.5, 1.0, Hitbox::circle(2.0));
+        assert_eq!(find_penetration(&circle_1, &circle_2), Vector2::zeros());
+        //Right on top of each other still gets pushed apart.
+        let circle_2 = shape(1.0, 1.0, Hitbox::circle(2.0));
+        assert_near(find_penetration(&circle_1, &circle_2), Vector2::new(0.0, 5.0));
+    }
 }
@@ -1,7 +1,10 @@
 use ggez::graphics::Vector2;
 use ggez::graphics::Point2;
+use utils::toml_float;
+use config::Status;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(from = "EventDef", into = "EventDef")]
 pub enum Event {
     ImpulseFrom { 
         from: Point2,
@@ -9,4 +12,47 @@ pub enum Event {
     },
     Impulse(Vector2),
     Damage(i32),
+    // Leaves a timed status on whatever gets the event.
+    Status(Status),
+}
+
+//Stand-in for Event when serializing. toml can only hold enums with data as tagged tables, and
+//serde can't combine that with the ggez vector types.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+enum EventDef {
+    ImpulseFrom { from: [f32; 2], magnitude: f32 },
+    Impulse([f32; 2]),
+    Damage(i32),
+    Status(Status),
+}
+
+impl From<Event> for EventDef {
+    fn from(event: Event) -> Self {
+        match event {
+            Event::ImpulseFrom { from, magnitude } => EventDef::ImpulseFrom {
+                from: [toml_float(from.x), toml_float(from.y)],
+                magnitude,
+            },
+            Event::Impulse(vector) => {
+                EventDef::Impulse([toml_float(vector.x), toml_float(vector.y)])
+            }
+            Event::Damage(damage) => EventDef::Damage(damage),
+            Event::Status(status) => EventDef::Status(status),
+        }
+    }
+}
+
+impl From<EventDef> for Event {
+    fn from(event: EventDef) -> Self {
+        match event {
+            EventDef::ImpulseFrom { from, magnitude } => Event::ImpulseFrom {
+                from: Point2::new(from[0], from[1]),
+                magnitude,
+            },
+            EventDef::Impulse(vector) => Event::Impulse(Vector2::new(vector[0], vector[1])),
+            EventDef::Damage(damage) => Event::Damage(damage),
+            EventDef::Status(status) => Event::Status(status),
+        }
+    }
 }
@@ -1,10 +1,18 @@
 use ggez::graphics::{Point2, Vector2};
 use ggez::graphics::Color;
+use rand::Rng;
 use super::collision::Hitbox;
 use super::event::Event;
 use super::basic_cuboid::BasicCuboid;
+use super::behaviour::{Behaviour, HasBehaviour, Transition};
+use super::behaviour::BehaviourState::*;
+use super::behaviour::Condition::*;
+use super::bullet::Bullet;
+use super::shooter::Shooter;
+use super::steering::{arrive, seek, truncate};
 use super::*;
 use assets::DrawableAsset;
+use config::{MobConfig, Status, SteeringConfig};
 
 pub trait IsMob {
     type Implmementation: HasPhysics + Renderable + CanRecieveEvents;
@@ -49,6 +57,14 @@ impl<T: IsMob> HasPhysics for T {
         self.get_mob().get_elasticity()
     }
 
+    fn get_velocity(&self) -> Vector2 {
+        self.get_mob().get_velocity()
+    }
+
+    fn get_inverse_mass(&self) -> f32 {
+        self.get_mob().get_inverse_mass()
+    }
+
     fn recieve_collision(&mut self, dt: f32, collision: collision::Collision) {
         self.get_mob_mut().recieve_collision(dt, collision);
     }
@@ -76,27 +92,142 @@ impl<T: IsMob> CanRecieveEvents for T {
     }
 }
 
+// How close a mob's center has to get to a waypoint to move on to the next.
+const WAYPOINT_RADIUS: f32 = 5.0;
+
+// Where a mob has been told to go, shared by every mob type.
+#[derive(Serialize, Deserialize)]
+pub struct Movement {
+    #[serde(default, with = "::utils::option_point_serde")]
+    target: Option<Point2>,
+    #[serde(default, with = "::utils::point_vec_serde")]
+    path: Vec<Point2>,
+    #[serde(default, with = "::utils::option_point_serde")]
+    path_target: Option<Point2>,
+    #[serde(default, with = "::utils::option_vector_serde")]
+    heading: Option<Vector2>,
+    #[serde(with = "::utils::vector_serde")]
+    steering_force: Vector2,
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Movement {
+            target: None,
+            path: Vec::new(),
+            path_target: None,
+            heading: None,
+            steering_force: Vector2::zeros(),
+        }
+    }
+}
+
+impl Movement {
+    // Heading straight for a new target drops any path.
+    pub fn set_target(&mut self, target: Point2) {
+        self.target = Some(target);
+        self.path.clear();
+        self.path_target = None;
+        self.heading = None;
+    }
+
+    pub fn get_target(&self) -> Option<Point2> {
+        self.target
+    }
+
+    pub fn set_path(&mut self, target: Point2, path: Vec<Point2>) {
+        self.path = path;
+        self.path_target = Some(target);
+        self.heading = None;
+    }
+
+    pub fn get_path_target(&self) -> Option<Point2> {
+        self.path_target
+    }
+
+    pub fn set_heading(&mut self, heading: Vector2) {
+        self.heading = Some(heading);
+        self.path.clear();
+        self.path_target = None;
+    }
+
+    pub fn set_steering_force(&mut self, force: Vector2) {
+        self.steering_force = force;
+    }
+
+    // The acceleration to pass to set_movement this step, for a mob with its center at center.
+    // It seeks the next waypoint, or else follows its heading, or else brings its center to its
+    // target, and the pull of its surroundings is added on top.
+    pub fn get_direction(&mut self, center: Point2, steering: &SteeringConfig) -> Vector2 {
+        while self.path.first().map_or(false, |w| (w - center).norm() < WAYPOINT_RADIUS) {
+            self.path.remove(0);
+        }
+        let goal = if let Some(&waypoint) = self.path.first() {
+            seek(center, waypoint)
+        } else if let Some(heading) = self.heading {
+            truncate(heading, 1.0)
+        } else if let Some(target) = self.target {
+            arrive(center, target, steering.arrive_radius)
+        } else {
+            Vector2::zeros()
+        };
+        steering.seek * goal + self.steering_force
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Dummy {
     implementation: BasicCuboid,
     blacklist: Vec<ObjectID>,
-    target: Option<Point2>,
+    movement: Movement,
+    contact_damage: i32,
+    knockback: f32,
+    #[serde(default)]
+    contact_statuses: Vec<Status>,
+    max_health: i32,
+    behaviour: Behaviour,
+    steering: SteeringConfig,
 }
 
+// Dummies rush the player, back off when badly hurt, and wander about when they've lost them.
+const DUMMY_TRANSITIONS: &[Transition] = &[
+    Transition { from: &[], to: Stunned, when: &[IsStunned] },
+    Transition { from: &[Stunned], to: Idle, when: &[NotStunned] },
+    Transition { from: &[Chase, Attack], to: Flee, when: &[HealthBelow(0.3), CloserThan(60.0)] },
+    Transition { from: &[Flee], to: Chase, when: &[FurtherThan(150.0)] },
+    Transition { from: &[Flee], to: Chase, when: &[After(3.0)] },
+    Transition { from: &[Idle, Patrol], to: Chase, when: &[SeesPlayer] },
+    Transition { from: &[Chase], to: Attack, when: &[SeesPlayer, CloserThan(30.0)] },
+    Transition { from: &[Attack], to: Chase, when: &[FurtherThan(40.0)] },
+    Transition { from: &[Attack], to: Chase, when: &[CantSeePlayer] },
+    Transition { from: &[Chase], to: Idle, when: &[LostPlayerFor(5.0)] },
+    Transition { from: &[Idle], to: Patrol, when: &[After(1.5)] },
+    Transition { from: &[Patrol], to: Idle, when: &[After(3.0)] },
+];
+
 impl Dummy {
-    pub fn new(position: Point2) -> Self {
+    pub fn new(position: Point2, config: &MobConfig) -> Self {
         Dummy {
             implementation: BasicCuboid::new(
-                1000.0,
-                DrawableAsset::Player,
+                config.acceleration,
+                DrawableAsset::Mob,
                 Vector2::new(10.0, 10.0),
-                2.0,
+                config.drag,
                 position,
-                5,
+                config.health,
                 ObjectID::new(0),
                 Color::from((222, 184, 135, 200)),
-            ),
+            ).with_hitbox(Hitbox::circle(5.0))
+                .with_elasticity(config.elasticity)
+                .with_mass(config.mass),
             blacklist: vec![ObjectID::new(1)],
-            target: None,
+            movement: Movement::default(),
+            contact_damage: config.contact_damage,
+            knockback: config.knockback,
+            contact_statuses: config.contact_statuses.clone(),
+            max_health: config.health,
+            behaviour: Behaviour::new(),
+            steering: config.steering,
         }
     }
 }
@@ -106,11 +237,12 @@ impl HasCollisionEvents for Dummy {
         //TODO move the blacklist thing to the other function place maybe?
         if self.blacklist.iter().any(|x| *x == id) {
             let mut effects = Vec::new();
-            effects.push(Event::Damage(1));
+            effects.push(Event::Damage(self.contact_damage));
             effects.push(Event::ImpulseFrom {
                 from: self.get_position(),
-                magnitude: 2000.0
+                magnitude: self.knockback,
             });
+            effects.extend(self.contact_statuses.iter().map(|&status| Event::Status(status)));
             effects
         } else {
             Vec::new()
@@ -120,17 +252,62 @@ impl HasCollisionEvents for Dummy {
 
 impl CanSetMoveTarget for Dummy {
     fn set_target(&mut self, target: Point2) {
-        self.target = Some(target);
+        self.movement.set_target(target);
+    }
+
+    fn get_target(&self) -> Option<Point2> {
+        self.movement.get_target()
+    }
+
+    fn set_path(&mut self, target: Point2, path: Vec<Point2>) {
+        self.movement.set_path(target, path);
+    }
+
+    fn get_path_target(&self) -> Option<Point2> {
+        self.movement.get_path_target()
+    }
+
+    fn set_heading(&mut self, heading: Vector2) {
+        self.movement.set_heading(heading);
+    }
+
+    fn get_steering(&self) -> SteeringConfig {
+        self.steering
+    }
+
+    fn set_steering_force(&mut self, force: Vector2) {
+        self.movement.set_steering_force(force);
+    }
+}
+
+impl HasBehaviour for Dummy {
+    fn get_behaviour(&self) -> &Behaviour {
+        &self.behaviour
+    }
+
+    fn get_behaviour_mut(&mut self) -> &mut Behaviour {
+        &mut self.behaviour
+    }
+
+    fn get_transitions(&self) -> &'static [Transition] {
+        DUMMY_TRANSITIONS
+    }
+
+    fn get_health_fraction(&self) -> f32 {
+        self.implementation.get_health() as f32 / self.max_health as f32
+    }
+
+    fn is_stunned(&self) -> bool {
+        self.implementation.is_stunned()
     }
 }
 
 impl IsMob for Dummy {
     type Implmementation = BasicCuboid;
     fn post_step(&mut self, _dt: f32) {
-        if let Some(t) = self.target {
-            let pos = self.get_position();
-            self.implementation.set_movement(t - pos);
-        }
+        let center = self.get_center_position();
+        let direction = self.movement.get_direction(center, &self.steering);
+        self.implementation.set_movement(direction);
     }
     fn get_mob_mut(&mut self) -> &mut Self::Implmementation {
         &mut self.implementation
@@ -139,3 +316,140 @@ impl IsMob for Dummy {
         &self.implementation
     }
 }
+
+// Any kind of mob, so that the World can keep them all in one list.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum Mob {
+    Dummy(Dummy),
+    Shooter(Shooter),
+}
+
+impl Mob {
+    fn get_ai(&self) -> &HasBehaviour {
+        match *self {
+            Mob::Dummy(ref mob) => mob,
+            Mob::Shooter(ref mob) => mob,
+        }
+    }
+
+    fn get_ai_mut(&mut self) -> &mut HasBehaviour {
+        match *self {
+            Mob::Dummy(ref mut mob) => mob,
+            Mob::Shooter(ref mut mob) => mob,
+        }
+    }
+
+    // Only does anything for mobs that can shoot.
+    pub fn set_aim(&mut self, aim: Option<(Point2, Vector2)>) {
+        if let Mob::Shooter(ref mut mob) = *self {
+            mob.set_aim(aim);
+        }
+    }
+}
+
+impl CanShoot for Mob {
+    fn shoot<R: Rng>(&mut self, rng: &mut R) -> Vec<Bullet> {
+        match *self {
+            Mob::Dummy(_) => Vec::new(),
+            Mob::Shooter(ref mut mob) => mob.shoot(rng),
+        }
+    }
+}
+
+impl HasCollisionEvents for Mob {
+    fn create_collision_event(&mut self, id: ObjectID) -> Vec<Event> {
+        match *self {
+            Mob::Dummy(ref mut mob) => mob.create_collision_event(id),
+            Mob::Shooter(ref mut mob) => mob.create_collision_event(id),
+        }
+    }
+}
+
+impl CanSetMoveTarget for Mob {
+    fn set_target(&mut self, target: Point2) {
+        self.get_ai_mut().set_target(target);
+    }
+
+    fn get_target(&self) -> Option<Point2> {
+        self.get_ai().get_target()
+    }
+
+    fn set_path(&mut self, target: Point2, path: Vec<Point2>) {
+        self.get_ai_mut().set_path(target, path);
+    }
+
+    fn get_path_target(&self) -> Option<Point2> {
+        self.get_ai().get_path_target()
+    }
+
+    fn set_heading(&mut self, heading: Vector2) {
+        self.get_ai_mut().set_heading(heading);
+    }
+
+    fn get_steering(&self) -> SteeringConfig {
+        self.get_ai().get_steering()
+    }
+
+    fn set_steering_force(&mut self, force: Vector2) {
+        self.get_ai_mut().set_steering_force(force);
+    }
+}
+
+impl HasBehaviour for Mob {
+    fn get_behaviour(&self) -> &Behaviour {
+        self.get_ai().get_behaviour()
+    }
+
+    fn get_behaviour_mut(&mut self) -> &mut Behaviour {
+        self.get_ai_mut().get_behaviour_mut()
+    }
+
+    fn get_transitions(&self) -> &'static [Transition] {
+        self.get_ai().get_transitions()
+    }
+
+    fn get_health_fraction(&self) -> f32 {
+        self.get_ai().get_health_fraction()
+    }
+
+    fn is_stunned(&self) -> bool {
+        self.get_ai().is_stunned()
+    }
+
+    fn get_preferred_distance(&self) -> f32 {
+        self.get_ai().get_preferred_distance()
+    }
+
+    fn get_attack_range(&self) -> f32 {
+        self.get_ai().get_attack_range()
+    }
+}
+
+impl IsMob for Mob {
+    type Implmementation = BasicCuboid;
+    fn pre_step(&mut self, dt: f32) {
+        match *self {
+            Mob::Dummy(ref mut mob) => mob.pre_step(dt),
+            Mob::Shooter(ref mut mob) => mob.pre_step(dt),
+        }
+    }
+    fn post_step(&mut self, dt: f32) {
+        match *self {
+            Mob::Dummy(ref mut mob) => mob.post_step(dt),
+            Mob::Shooter(ref mut mob) => mob.post_step(dt),
+        }
+    }
+    fn get_mob_mut(&mut self) -> &mut Self::Implmementation {
+        match *self {
+            Mob::Dummy(ref mut mob) => mob.get_mob_mut(),
+            Mob::Shooter(ref mut mob) => mob.get_mob_mut(),
+        }
+    }
+    fn get_mob(&self) -> &Self::Implmementation {
+        match *self {
+            Mob::Dummy(ref mob) => mob.get_mob(),
+            Mob::Shooter(ref mob) => mob.get_mob(),
+        }
+    }
+}
@@ -1,22 +1,32 @@
 mod physics;
+pub mod broadphase;
 pub mod bullet;
 pub mod event;
 pub mod collision;
 pub mod player;
 pub mod mob;
+pub mod shooter;
 pub mod block;
 pub mod basic_cuboid;
+pub mod behaviour;
+pub mod raycast;
+pub mod steering;
+pub mod weapon;
+pub mod status;
 
 use ggez::graphics::Point2;
 use ggez::graphics::Vector2;
 use ggez::graphics::Color;
+use rand::Rng;
 use self::collision::Hitbox;
-use self::collision::is_intersecting;
+use self::collision::sweep;
 use self::event::Event;
+use self::broadphase::{SpatialHash, CELL_SIZE};
 use assets::DrawableAsset;
+use config::SteeringConfig;
 
 // structs
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ObjectID {
     value: u32,
 }
@@ -34,15 +44,48 @@ impl ObjectID {
 
 pub trait CanSetMoveTarget: Object {
     fn set_target(&mut self, target: Point2);
+
+    fn get_target(&self) -> Option<Point2> {
+        None
+    }
+
+    // Waypoints leading to target, nearest first. Once they run out the mob heads straight for
+    // its target.
+    fn set_path(&mut self, _target: Point2, _path: Vec<Point2>) {
+        //do nothing
+    }
+
+    // The target the current path was found for.
+    fn get_path_target(&self) -> Option<Point2> {
+        None
+    }
+
+    // A direction to walk in, in place of the target and path, until the next set_target.
+    fn set_heading(&mut self, _heading: Vector2) {
+        //do nothing
+    }
+
+    fn get_steering(&self) -> SteeringConfig;
+
+    // The pull of the mob's surroundings, such as other mobs and walls, already weighted. Added on
+    // to wherever the mob is headed.
+    fn set_steering_force(&mut self, _force: Vector2) {
+        //do nothing
+    }
 }
 
 pub trait CanShoot: Object {
-    fn shoot(&mut self) -> Option<bullet::Bullet>;
+    // Any randomness, such as spread, comes from rng.
+    fn shoot<R: Rng>(&mut self, rng: &mut R) -> Vec<bullet::Bullet>;
 }
 
 pub trait Renderable: HasBoundingBox {
     fn get_drawable_asset(&self) -> DrawableAsset;
     fn get_color(&self) -> Option<Color>;
+
+    fn get_scale(&self) -> Point2 {
+        Point2::new(1.0, 1.0)
+    }
 }
 
 pub trait HasBoundingBox: Object {
@@ -55,6 +98,11 @@ pub trait HasBoundingBox: Object {
 
 pub trait HasHitbox: Object {
     fn get_hitbox(&self) -> &Hitbox;
+
+    // How far the hitbox moved during the last step, for objects fast enough to need sweeping.
+    fn get_displacement(&self) -> Vector2 {
+        Vector2::zeros()
+    }
 }
 
 pub trait HasCollision: HasHitbox {
@@ -72,6 +120,15 @@ pub trait HasPhysics: HasHitbox {
         0.0
     }
 
+    fn get_velocity(&self) -> Vector2 {
+        Vector2::zeros()
+    }
+
+    // Zero for bodies that can't be pushed, such as walls.
+    fn get_inverse_mass(&self) -> f32 {
+        0.0
+    }
+
     fn recieve_collision(&mut self, _dt: f32, _collision: collision::Collision) {
         //do nothing
     }
@@ -100,6 +157,20 @@ pub trait CanRecieveEvents: Object {
 pub trait HasCollisionEvents: HasHitbox {
     fn create_collision_event(&mut self, id: ObjectID)
         -> Vec<Event>;
+
+    fn can_hit(&self, _id: ObjectID) -> bool {
+        true
+    }
+
+    // Told the time of impact of everything this could hit this step, before any events are
+    // created.
+    fn offer_hit(&mut self, _time: f32) {
+        //do nothing
+    }
+
+    fn accepts_hit(&self, _time: f32) -> bool {
+        true
+    }
 }
 
 pub trait Object {
@@ -129,19 +200,14 @@ where
 }
 
 //functions
+// Events don't move anything, so a grid built up front stays valid for the whole pass.
 pub fn object_vec_collision_events<T: HasHitbox + CanRecieveEvents, U: HasCollisionEvents>(
     dt: f32,
     object_1: &mut T,
     list: &mut Vec<U>,
 ) {
-    for object_2 in list.iter_mut() {
-        if is_intersecting(object_1, object_2) {
-            let events = object_2.create_collision_event(object_1.get_id());
-            for event in events {
-                object_1.recieve_event(dt, event);
-            }
-        }
-    }
+    let grid = SpatialHash::from_list(CELL_SIZE, list);
+    grid_collision_events(dt, object_1, list, &grid);
 }
 
 pub fn vec_vec_collision_events<T: HasHitbox + CanRecieveEvents, U: HasCollisionEvents>(
@@ -149,9 +215,22 @@ pub fn vec_vec_collision_events<T: HasHitbox + CanRecieveEvents, U: HasCollision
     objects_1: &mut Vec<T>,
     objects_2: &mut Vec<U>,
 ) {
+    let grid = SpatialHash::from_list(CELL_SIZE, objects_2);
     for object_1 in objects_1.iter_mut() {
-        for object_2 in objects_2.iter_mut() {
-            if is_intersecting(object_1, object_2) {
+        grid_collision_events(dt, object_1, objects_2, &grid);
+    }
+}
+
+fn grid_collision_events<T: HasHitbox + CanRecieveEvents, U: HasCollisionEvents>(
+    dt: f32,
+    object_1: &mut T,
+    list: &mut [U],
+    grid: &SpatialHash,
+) {
+    for j in grid.query_object(object_1) {
+        let object_2 = &mut list[j];
+        if let Some(hit) = sweep(object_1, object_2) {
+            if object_2.accepts_hit(hit.get_time()) {
                 let events = object_2.create_collision_event(object_1.get_id());
                 for event in events {
                     object_1.recieve_event(dt, event);
@@ -160,3 +239,35 @@ pub fn vec_vec_collision_events<T: HasHitbox + CanRecieveEvents, U: HasCollision
         }
     }
 }
+
+// Let every object in list know when it would hit object_1, so that it can pick the first thing
+// in its path before the events are handed out.
+pub fn object_vec_first_hits<T: HasHitbox, U: HasCollisionEvents>(object_1: &T, list: &mut Vec<U>) {
+    let grid = SpatialHash::from_list(CELL_SIZE, list);
+    grid_first_hits(object_1, list, &grid);
+}
+
+pub fn vec_vec_first_hits<T: HasHitbox, U: HasCollisionEvents>(
+    objects_1: &Vec<T>,
+    objects_2: &mut Vec<U>,
+) {
+    let grid = SpatialHash::from_list(CELL_SIZE, objects_2);
+    for object_1 in objects_1.iter() {
+        grid_first_hits(object_1, objects_2, &grid);
+    }
+}
+
+fn grid_first_hits<T: HasHitbox, U: HasCollisionEvents>(
+    object_1: &T,
+    list: &mut [U],
+    grid: &SpatialHash,
+) {
+    for j in grid.query_object(object_1) {
+        let object_2 = &mut list[j];
+        if object_2.can_hit(object_1.get_id()) {
+            if let Some(hit) = sweep(object_1, object_2) {
+                object_2.offer_hit(hit.get_time());
+            }
+        }
+    }
+}
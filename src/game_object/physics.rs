@@ -1,7 +1,9 @@
 use ggez::graphics::Vector2;
 
+#[derive(Serialize, Deserialize)]
 struct Drag {
     drag_constant: f32,
+    #[serde(with = "::utils::vector_serde")]
     pub acceleration: Vector2,
 }
 
@@ -18,10 +20,14 @@ impl Drag {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct ActorPhysics {
+    #[serde(with = "::utils::vector_serde")]
     velocity: Vector2,
+    #[serde(with = "::utils::vector_serde")]
     acceleration: Vector2,
     drag: Drag,
+    #[serde(with = "::utils::vector_serde")]
     facing: Vector2,
 }
 
@@ -1,25 +1,32 @@
 use ggez::graphics::{Point2, Vector2};
 use ggez::graphics::Color;
+use rand::Rng;
 use super::*;
 use super::mob::IsMob;
 use super::basic_cuboid::BasicCuboid;
 use super::physics::ActorPhysics;
 use super::collision::Hitbox;
 use super::event::Event;
+use super::status::StatusEffects;
+use super::weapon::Weapon;
 use assets::DrawableAsset;
+use config::PlayerConfig;
 
+#[derive(Serialize, Deserialize)]
 pub struct Player {
     implementation: BasicCuboid,
-    time_since_shot: f32,
+    weapons: Vec<Weapon>,
+    weapon_index: usize,
+    #[serde(with = "::utils::vector_serde")]
     shoot_direction: Vector2,
 }
 
 impl Player {
-    pub fn new() -> Self {
+    pub fn new(position: Point2, config: &PlayerConfig) -> Self {
         //TODO write this in terms of max speed and seconds until max speed is hit. You need
         //calculus.
-        let accel = 2500.0;
-        let max_speed = 250.0;
+        let accel = config.acceleration;
+        let max_speed = config.max_speed;
         let drag_constant = accel / max_speed;
         // ex. with v^2 drag
         // accel_drag = v^2*C
@@ -34,12 +41,14 @@ impl Player {
                 DrawableAsset::Player,
                 Vector2::new(10.0, 10.0),
                 drag_constant,
-                Point2::new(0.0, 0.0),
-                30,
+                position,
+                config.health,
                 ObjectID::new(1),
                 Color::new(0.3, 0.7, 0.7, 0.7),
-            ),
-            time_since_shot: 300.0,
+            ).with_elasticity(config.elasticity)
+                .with_mass(config.mass),
+            weapons: config.weapons.iter().map(Weapon::new).collect(),
+            weapon_index: 0,
             shoot_direction: Vector2::new(0.0, 0.0),
         }
     }
@@ -54,20 +63,48 @@ impl Player {
     pub fn get_health(&self) -> i32 {
         self.implementation.get_health()
     }
+
+    pub fn get_statuses(&self) -> &StatusEffects {
+        self.implementation.get_statuses()
+    }
+
+    // None if the player has no weapons at all.
+    pub fn get_weapon(&self) -> Option<&Weapon> {
+        self.weapons.get(self.weapon_index)
+    }
+
+    pub fn get_weapon_index(&self) -> usize {
+        self.weapon_index
+    }
+
+    pub fn get_weapon_count(&self) -> usize {
+        self.weapons.len()
+    }
+
+    // Indices past the end of the player's weapons are ignored. Putting a weapon away stops it
+    // reloading.
+    pub fn switch_weapon(&mut self, index: usize) {
+        if index < self.weapons.len() && index != self.weapon_index {
+            self.weapons[self.weapon_index].cancel_reload();
+            self.weapon_index = index;
+        }
+    }
+
+    pub fn reload(&mut self) {
+        if let Some(weapon) = self.weapons.get_mut(self.weapon_index) {
+            weapon.reload();
+        }
+    }
 }
 
 impl CanShoot for Player {
-    fn shoot(&mut self) -> Option<bullet::Bullet> {
-        if self.time_since_shot >= 0.10 {
-            self.time_since_shot = 0.0;
-            Some(bullet::Bullet::new(
-                self.get_center_position(),
-                500.0 * self.shoot_direction.normalize(),
-                Color::new(0.9, 0.9, 0.9, 1.0),
-                vec![self.get_id()],
-            ))
-        } else {
-            None
+    fn shoot<R: Rng>(&mut self, rng: &mut R) -> Vec<bullet::Bullet> {
+        let origin = self.get_center_position();
+        let (direction, id) = (self.shoot_direction, self.get_id());
+        let color = Color::new(0.9, 0.9, 0.9, 1.0);
+        match self.weapons.get_mut(self.weapon_index) {
+            Some(weapon) => weapon.fire(origin, direction, color, &[id], rng),
+            None => Vec::new(),
         }
     }
 }
@@ -75,8 +112,8 @@ impl CanShoot for Player {
 impl IsMob for Player {
     type Implmementation = BasicCuboid;
     fn pre_step(&mut self, dt: f32) {
-        if self.time_since_shot < 200.0 {
-            self.time_since_shot += dt;
+        for weapon in &mut self.weapons {
+            weapon.step(dt);
         }
     }
     fn get_mob_mut(&mut self) -> &mut Self::Implmementation {
@@ -0,0 +1,108 @@
+use ggez::graphics::{Point2, Vector2};
+use super::HasHitbox;
+use super::collision::{ray_box, ray_circle, ray_polygon, Hitbox};
+
+// The kinds of object a ray can be asked to hit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Category {
+    Player,
+    Mob,
+    Block,
+}
+
+// The first thing a ray hit. Index is where the object is in the World's list for its category,
+// and time goes from 0 at the start of the ray to 1 at the end.
+#[derive(Clone, Copy)]
+pub struct RayHit {
+    category: Category,
+    index: usize,
+    time: f32,
+    point: Point2,
+    normal: Vector2,
+}
+
+impl RayHit {
+    pub fn new(category: Category, index: usize, time: f32, point: Point2, normal: Vector2) -> Self {
+        Self {
+            category,
+            index,
+            time,
+            point,
+            normal,
+        }
+    }
+
+    pub fn get_category(&self) -> Category {
+        self.category
+    }
+
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn get_point(&self) -> Point2 {
+        self.point
+    }
+
+    // Unit vector out of the surface that was hit.
+    pub fn get_normal(&self) -> Vector2 {
+        self.normal
+    }
+
+    // Whichever of the two hits comes first along the ray.
+    pub fn first(hit_1: Option<RayHit>, hit_2: Option<RayHit>) -> Option<RayHit> {
+        match (hit_1, hit_2) {
+            (Some(hit_1), Some(hit_2)) => if hit_2.time < hit_1.time {
+                Some(hit_2)
+            } else {
+                Some(hit_1)
+            },
+            (Some(hit), None) | (None, Some(hit)) => Some(hit),
+            (None, None) => None,
+        }
+    }
+}
+
+// Where the ray from origin along delta first enters the object. Rays that start inside an object
+// don't hit it, so a mob looking out from its own center doesn't see itself.
+pub fn raycast_object<T: HasHitbox>(
+    origin: Point2,
+    delta: Vector2,
+    object: &T,
+) -> Option<(f32, Vector2)> {
+    let position = object.get_position();
+    match *object.get_hitbox() {
+        Hitbox::Aabb(size) => ray_box(origin, delta, position, position + size),
+        Hitbox::Circle(radius) => ray_circle(
+            origin,
+            delta,
+            position + Vector2::new(radius, radius),
+            radius,
+        ),
+        Hitbox::Polygon(ref points) => {
+            let points: Vec<Point2> = points.iter().map(|point| position + point).collect();
+            ray_polygon(origin, delta, &points)
+        }
+    }
+}
+
+// The first object in list along the ray, labelled with category.
+pub fn raycast_vec<T: HasHitbox>(
+    origin: Point2,
+    delta: Vector2,
+    list: &[T],
+    category: Category,
+) -> Option<RayHit> {
+    let mut first = None;
+    for (index, object) in list.iter().enumerate() {
+        if let Some((time, normal)) = raycast_object(origin, delta, object) {
+            let hit = RayHit::new(category, index, time, origin + time * delta, normal);
+            first = RayHit::first(first, Some(hit));
+        }
+    }
+    first
+}
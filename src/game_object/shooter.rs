@@ -0,0 +1,267 @@
+use ggez::graphics::{Point2, Vector2};
+use ggez::graphics::Color;
+use rand::Rng;
+use super::basic_cuboid::BasicCuboid;
+use super::behaviour::{Behaviour, HasBehaviour, Transition};
+use super::behaviour::BehaviourState::*;
+use super::behaviour::Condition::*;
+use super::bullet::Bullet;
+use super::collision::Hitbox;
+use super::event::Event;
+use super::mob::{IsMob, Movement};
+use super::weapon::Weapon;
+use super::*;
+use assets::DrawableAsset;
+use config::{ShooterConfig, SteeringConfig};
+
+// Shooters close in until they have a shot, then hold their distance and fire.
+const SHOOTER_TRANSITIONS: &[Transition] = &[
+    Transition { from: &[], to: Stunned, when: &[IsStunned] },
+    Transition { from: &[Stunned], to: Idle, when: &[NotStunned] },
+    Transition { from: &[Idle, Patrol], to: Chase, when: &[SeesPlayer] },
+    Transition { from: &[Chase], to: Attack, when: &[SeesPlayer, InAttackRange] },
+    Transition { from: &[Attack], to: Chase, when: &[OutOfAttackRange(40.0)] },
+    Transition { from: &[Attack], to: Chase, when: &[CantSeePlayer] },
+    Transition { from: &[Chase], to: Idle, when: &[LostPlayerFor(5.0)] },
+    Transition { from: &[Idle], to: Patrol, when: &[After(1.5)] },
+    Transition { from: &[Patrol], to: Idle, when: &[After(3.0)] },
+];
+
+#[derive(Serialize, Deserialize)]
+pub struct Shooter {
+    implementation: BasicCuboid,
+    movement: Movement,
+    max_health: i32,
+    behaviour: Behaviour,
+    weapon: Weapon,
+    whitelist: Vec<ObjectID>,
+    // Where the player is and how fast they're moving, while the Shooter has a shot at them.
+    #[serde(default, with = "::utils::option_point_serde")]
+    aim_point: Option<Point2>,
+    #[serde(with = "::utils::vector_serde")]
+    aim_velocity: Vector2,
+    steering: SteeringConfig,
+    preferred_distance: f32,
+    attack_range: f32,
+}
+
+impl Shooter {
+    pub fn new(position: Point2, config: &ShooterConfig) -> Self {
+        Shooter {
+            implementation: BasicCuboid::new(
+                config.acceleration,
+                DrawableAsset::Mob,
+                Vector2::new(10.0, 10.0),
+                config.drag,
+                position,
+                config.health,
+                ObjectID::new(0),
+                Color::from((205, 92, 92, 200)),
+            ).with_hitbox(Hitbox::circle(5.0))
+                .with_elasticity(config.elasticity)
+                .with_mass(config.mass),
+            movement: Movement::default(),
+            max_health: config.health,
+            behaviour: Behaviour::new(),
+            weapon: Weapon::new(&config.weapon),
+            whitelist: vec![ObjectID::new(0)],
+            aim_point: None,
+            aim_velocity: Vector2::zeros(),
+            steering: config.steering,
+            preferred_distance: config.preferred_distance,
+            attack_range: config.attack_range,
+        }
+    }
+
+    // Give the Shooter something to shoot at, moving at velocity, or None to hold fire.
+    pub fn set_aim(&mut self, aim: Option<(Point2, Vector2)>) {
+        match aim {
+            Some((point, velocity)) => {
+                self.aim_point = Some(point);
+                self.aim_velocity = velocity;
+            }
+            None => {
+                self.aim_point = None;
+                self.aim_velocity = Vector2::zeros();
+            }
+        }
+    }
+}
+
+// The direction to fire in from origin for a bullet at speed to meet something at target moving
+// at velocity. Aims straight at the target when the bullet can't catch it.
+pub fn lead_target(origin: Point2, speed: f32, target: Point2, velocity: Vector2) -> Vector2 {
+    let offset = target - origin;
+    // Solves |offset + velocity * t| = speed * t for the earliest t after now.
+    let a = velocity.dot(&velocity) - speed * speed;
+    let b = 2.0 * offset.dot(&velocity);
+    let c = offset.dot(&offset);
+    let time = if a.abs() < 1e-6 {
+        if b < 0.0 {
+            -c / b
+        } else {
+            -1.0
+        }
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            -1.0
+        } else {
+            let root = discriminant.sqrt();
+            let (t1, t2) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+            let (early, late) = (t1.min(t2), t1.max(t2));
+            if early > 0.0 {
+                early
+            } else {
+                late
+            }
+        }
+    };
+    if time > 0.0 {
+        offset + time * velocity
+    } else {
+        offset
+    }
+}
+
+impl CanShoot for Shooter {
+    fn shoot<R: Rng>(&mut self, rng: &mut R) -> Vec<Bullet> {
+        let target = match self.aim_point {
+            Some(target) => target,
+            None => return Vec::new(),
+        };
+        let origin = self.get_center_position();
+        let direction = lead_target(origin, self.weapon.get_speed(), target, self.aim_velocity);
+        let color = Color::new(0.9, 0.5, 0.3, 1.0);
+        self.weapon.fire(origin, direction, color, &self.whitelist, rng)
+    }
+}
+
+// Shooters only hurt the player with their bullets.
+impl HasCollisionEvents for Shooter {
+    fn create_collision_event(&mut self, _id: ObjectID) -> Vec<Event> {
+        Vec::new()
+    }
+}
+
+impl CanSetMoveTarget for Shooter {
+    fn set_target(&mut self, target: Point2) {
+        self.movement.set_target(target);
+    }
+
+    fn get_target(&self) -> Option<Point2> {
+        self.movement.get_target()
+    }
+
+    fn set_path(&mut self, target: Point2, path: Vec<Point2>) {
+        self.movement.set_path(target, path);
+    }
+
+    fn get_path_target(&self) -> Option<Point2> {
+        self.movement.get_path_target()
+    }
+
+    fn set_heading(&mut self, heading: Vector2) {
+        self.movement.set_heading(heading);
+    }
+
+    fn get_steering(&self) -> SteeringConfig {
+        self.steering
+    }
+
+    fn set_steering_force(&mut self, force: Vector2) {
+        self.movement.set_steering_force(force);
+    }
+}
+
+impl HasBehaviour for Shooter {
+    fn get_behaviour(&self) -> &Behaviour {
+        &self.behaviour
+    }
+
+    fn get_behaviour_mut(&mut self) -> &mut Behaviour {
+        &mut self.behaviour
+    }
+
+    fn get_transitions(&self) -> &'static [Transition] {
+        SHOOTER_TRANSITIONS
+    }
+
+    fn get_health_fraction(&self) -> f32 {
+        self.implementation.get_health() as f32 / self.max_health as f32
+    }
+
+    fn is_stunned(&self) -> bool {
+        self.implementation.is_stunned()
+    }
+
+    fn get_preferred_distance(&self) -> f32 {
+        self.preferred_distance
+    }
+
+    fn get_attack_range(&self) -> f32 {
+        self.attack_range
+    }
+}
+
+impl IsMob for Shooter {
+    type Implmementation = BasicCuboid;
+    fn pre_step(&mut self, dt: f32) {
+        self.weapon.step(dt);
+    }
+    fn post_step(&mut self, _dt: f32) {
+        let center = self.get_center_position();
+        let direction = self.movement.get_direction(center, &self.steering);
+        self.implementation.set_movement(direction);
+    }
+    fn get_mob_mut(&mut self) -> &mut Self::Implmementation {
+        &mut self.implementation
+    }
+    fn get_mob(&self) -> &Self::Implmementation {
+        &self.implementation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_direction(direction: Vector2, expected: Vector2) {
+        let error = (direction.normalize() - expected.normalize()).norm();
+        assert!(error < 1e-4, "aimed along {:?}, not {:?}", direction, expected);
+    }
+
+    #[test]
+    fn lead_target_aims_straight_at_a_stationary_target() {
+        let origin = Point2::new(10.0, 10.0);
+        let direction = lead_target(origin, 100.0, Point2::new(40.0, 50.0), Vector2::zeros());
+        assert_direction(direction, Vector2::new(30.0, 40.0));
+    }
+
+    #[test]
+    fn lead_target_leads_a_perpendicular_mover() {
+        //The bullet meets the target after 1.25 seconds, 75 further along.
+        let origin = Point2::new(0.0, 0.0);
+        let (target, velocity) = (Point2::new(100.0, 0.0), Vector2::new(0.0, 60.0));
+        let direction = lead_target(origin, 100.0, target, velocity);
+        assert_direction(direction, Vector2::new(100.0, 75.0));
+        //Coming in as fast as the bullet, they meet after 5/6 of a second.
+        let direction = lead_target(origin, 100.0, target, Vector2::new(-60.0, 80.0));
+        assert_direction(direction, Vector2::new(50.0, 200.0 / 3.0));
+    }
+
+    #[test]
+    fn lead_target_aims_straight_at_an_unreachable_target() {
+        let origin = Point2::new(0.0, 0.0);
+        let target = Point2::new(100.0, 0.0);
+        //Running away faster than the bullet.
+        let direction = lead_target(origin, 100.0, target, Vector2::new(200.0, 0.0));
+        assert_direction(direction, Vector2::new(1.0, 0.0));
+        //Crossing faster than the bullet.
+        let direction = lead_target(origin, 100.0, target, Vector2::new(0.0, 200.0));
+        assert_direction(direction, Vector2::new(1.0, 0.0));
+        //Running away exactly as fast as the bullet.
+        let direction = lead_target(origin, 100.0, target, Vector2::new(100.0, 0.0));
+        assert_direction(direction, Vector2::new(1.0, 0.0));
+    }
+}
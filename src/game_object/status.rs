@@ -0,0 +1,229 @@
+use std::cmp::Ordering;
+use std::mem;
+use config::Status;
+
+// Seconds between each bit of damage over time.
+const DAMAGE_INTERVAL: f32 = 0.5;
+
+// The most poisons that can be on one thing at once.
+const MAX_POISON_STACKS: usize = 5;
+
+// How a new status combines with others of the same kind already running.
+enum Stacking {
+    // One at a time. A stronger new one takes over for its own duration, even if that's shorter
+    // than what was left. Any other new one just tops the timer back up to its duration.
+    Refresh,
+    // One at a time. A new one only ever makes it last longer.
+    Extend,
+    // Up to this many at once, each running out on its own. Past that, a new one replaces the one
+    // nearest to running out.
+    Stack(usize),
+}
+
+fn get_stacking(status: &Status) -> Stacking {
+    match *status {
+        Status::Slow { .. } | Status::Burn { .. } => Stacking::Refresh,
+        Status::Stun { .. } => Stacking::Extend,
+        Status::Poison { .. } => Stacking::Stack(MAX_POISON_STACKS),
+    }
+}
+
+// Whether new is stronger than old, taking them to be the same kind.
+fn is_stronger(new: &Status, old: &Status) -> bool {
+    match (*new, *old) {
+        (Status::Slow { multiplier: a, .. }, Status::Slow { multiplier: b, .. }) => a < b,
+        (Status::Burn { damage: a, .. }, Status::Burn { damage: b, .. })
+        | (Status::Poison { damage: a, .. }, Status::Poison { damage: b, .. }) => a > b,
+        _ => false,
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct StatusEffect {
+    status: Status,
+    remaining: f32,
+    // Counts down to the next bit of damage over time.
+    damage_timer: f32,
+}
+
+impl StatusEffect {
+    fn new(status: Status) -> Self {
+        Self {
+            status,
+            remaining: status.get_duration(),
+            damage_timer: DAMAGE_INTERVAL,
+        }
+    }
+}
+
+// The timed statuses on something, ticked along with it.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    pub fn apply(&mut self, status: Status) {
+        if !(status.get_duration() > 0.0) {
+            return;
+        }
+        let kind = mem::discriminant(&status);
+        let mut same_kind = self.effects
+            .iter()
+            .enumerate()
+            .filter(|&(_, effect)| mem::discriminant(&effect.status) == kind)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        match get_stacking(&status) {
+            Stacking::Refresh => match same_kind.pop() {
+                // Keeps the old damage timer, so being hit over and over still deals damage.
+                Some(i) => {
+                    let effect = &mut self.effects[i];
+                    if is_stronger(&status, &effect.status) {
+                        effect.status = status;
+                        effect.remaining = status.get_duration();
+                    } else {
+                        effect.remaining = effect.remaining.max(status.get_duration());
+                    }
+                }
+                None => self.effects.push(StatusEffect::new(status)),
+            },
+            Stacking::Extend => match same_kind.pop() {
+                Some(i) => {
+                    let effect = &mut self.effects[i];
+                    if status.get_duration() > effect.remaining {
+                        effect.status = status;
+                        effect.remaining = status.get_duration();
+                    }
+                }
+                None => self.effects.push(StatusEffect::new(status)),
+            },
+            Stacking::Stack(max) => if same_kind.len() < max {
+                self.effects.push(StatusEffect::new(status));
+            } else {
+                let oldest = {
+                    let effects = &self.effects;
+                    same_kind.into_iter().min_by(|&a, &b| {
+                        effects[a]
+                            .remaining
+                            .partial_cmp(&effects[b].remaining)
+                            .unwrap_or(Ordering::Equal)
+                    })
+                };
+                if let Some(i) = oldest {
+                    self.effects[i] = StatusEffect::new(status);
+                }
+            },
+        }
+    }
+
+    // Advance by dt, dropping statuses that run out, and return the damage over time dealt.
+    pub fn step(&mut self, dt: f32) -> i32 {
+        let mut damage = 0;
+        for effect in &mut self.effects {
+            effect.remaining -= dt;
+            let per_tick = match effect.status {
+                Status::Poison { damage, .. } | Status::Burn { damage, .. } => damage,
+                _ => continue,
+            };
+            effect.damage_timer -= dt;
+            while effect.damage_timer <= 0.0 {
+                damage += per_tick;
+                effect.damage_timer += DAMAGE_INTERVAL;
+            }
+        }
+        self.effects.retain(|effect| effect.remaining > 0.0);
+        damage
+    }
+
+    // What walking acceleration is scaled by.
+    pub fn get_speed_multiplier(&self) -> f32 {
+        self.effects
+            .iter()
+            .map(|effect| match effect.status {
+                Status::Slow { multiplier, .. } => multiplier.max(0.0),
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.effects.iter().any(|effect| match effect.status {
+            Status::Stun { .. } => true,
+            _ => false,
+        })
+    }
+
+    // Each kind of status running, with how many of it, like "slow, poison x2".
+    pub fn describe(&self) -> String {
+        let mut names: Vec<(&str, usize)> = Vec::new();
+        for effect in &self.effects {
+            let name = effect.status.get_name();
+            match names.iter().position(|&(other, _)| other == name) {
+                Some(i) => names[i].1 += 1,
+                None => names.push((name, 1)),
+            }
+        }
+        names
+            .iter()
+            .map(|&(name, count)| if count > 1 {
+                format!("{} x{}", name, count)
+            } else {
+                name.to_owned()
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32;
+    use super::*;
+
+    fn get_remaining(effects: &StatusEffects) -> Vec<f32> {
+        effects.effects.iter().map(|effect| effect.remaining).collect()
+    }
+
+    #[test]
+    fn refresh_takes_the_stronger_status_and_its_duration() {
+        let mut effects = StatusEffects::default();
+        effects.apply(Status::Slow { multiplier: 0.5, duration: 3.0 });
+        //A weaker one never cuts the time short.
+        effects.apply(Status::Slow { multiplier: 0.8, duration: 1.0 });
+        assert_eq!(get_remaining(&effects), vec![3.0]);
+        assert_eq!(effects.get_speed_multiplier(), 0.5);
+        //A stronger one takes over for its own duration.
+        effects.apply(Status::Slow { multiplier: 0.2, duration: 1.0 });
+        assert_eq!(get_remaining(&effects), vec![1.0]);
+        assert_eq!(effects.get_speed_multiplier(), 0.2);
+        //A weaker one tops the stronger one back up.
+        effects.step(0.75);
+        effects.apply(Status::Slow { multiplier: 0.5, duration: 2.0 });
+        assert_eq!(get_remaining(&effects), vec![2.0]);
+        assert_eq!(effects.get_speed_multiplier(), 0.2);
+        effects.apply(Status::Slow { multiplier: 0.2, duration: 0.5 });
+        assert_eq!(get_remaining(&effects), vec![2.0]);
+    }
+
+    #[test]
+    fn bad_durations_are_ignored() {
+        let mut effects = StatusEffects::default();
+        for &duration in &[0.0, -1.0, f32::NAN] {
+            effects.apply(Status::Stun { duration });
+            effects.apply(Status::Poison { damage: 1, duration });
+        }
+        assert!(effects.effects.is_empty());
+    }
+
+    #[test]
+    fn poison_replaces_the_stack_nearest_to_running_out() {
+        let mut effects = StatusEffects::default();
+        for i in 0..MAX_POISON_STACKS {
+            effects.apply(Status::Poison { damage: 1, duration: 5.0 + i as f32 });
+        }
+        effects.apply(Status::Poison { damage: 2, duration: 1.0 });
+        assert_eq!(get_remaining(&effects), vec![1.0, 6.0, 7.0, 8.0, 9.0]);
+        assert_eq!(effects.describe(), "poison x5");
+    }
+}
@@ -0,0 +1,96 @@
+use std::f32;
+use ggez::graphics::{Point2, Vector2};
+use super::raycast::RayHit;
+
+// How far ahead of a mob its feelers reach, looking for walls.
+pub const FEELER_LENGTH: f32 = 25.0;
+
+// The angle between the middle feeler and the ones either side of it.
+const FEELER_SPREAD: f32 = f32::consts::PI / 6.0;
+
+// Shortens vector to length max if it's any longer.
+pub fn truncate(vector: Vector2, max: f32) -> Vector2 {
+    let length = vector.norm();
+    if length > max {
+        max * vector / length
+    } else {
+        vector
+    }
+}
+
+// Full speed towards target.
+pub fn seek(from: Point2, target: Point2) -> Vector2 {
+    let offset = target - from;
+    let distance = offset.norm();
+    if distance > 0.0 {
+        offset / distance
+    } else {
+        Vector2::zeros()
+    }
+}
+
+// Like seek, but easing off within radius of the target so as to stop on it.
+pub fn arrive(from: Point2, target: Point2, radius: f32) -> Vector2 {
+    let offset = target - from;
+    if radius > 0.0 {
+        truncate(offset / radius, 1.0)
+    } else {
+        seek(from, target)
+    }
+}
+
+// Away from every neighbour within radius, pushing harder the closer they are. Neighbours right
+// on top of the mob are left to the physics, since there's no telling which way to go.
+pub fn separation(center: Point2, neighbours: &[Point2], radius: f32) -> Vector2 {
+    let mut force = Vector2::zeros();
+    for neighbour in neighbours {
+        let offset = center - neighbour;
+        let distance = offset.norm();
+        if distance > 0.0 && distance < radius {
+            force += (1.0 - distance / radius) * offset / distance;
+        }
+    }
+    truncate(force, 1.0)
+}
+
+// Towards the average position of the neighbours within radius.
+pub fn cohesion(center: Point2, neighbours: &[Point2], radius: f32) -> Vector2 {
+    let mut total = Vector2::zeros();
+    let mut count = 0;
+    for neighbour in neighbours {
+        let offset = neighbour - center;
+        if offset.norm() < radius {
+            total += offset;
+            count += 1;
+        }
+    }
+    if count == 0 || radius <= 0.0 {
+        return Vector2::zeros();
+    }
+    truncate(total / (count as f32 * radius), 1.0)
+}
+
+// Three rays fanning out ahead of a mob moving at velocity, each FEELER_LENGTH long. None when it
+// isn't moving.
+pub fn get_feelers(velocity: Vector2) -> Vec<Vector2> {
+    let speed = velocity.norm();
+    if speed == 0.0 {
+        return Vec::new();
+    }
+    let ahead = FEELER_LENGTH * velocity / speed;
+    let (sin, cos) = FEELER_SPREAD.sin_cos();
+    vec![
+        ahead,
+        Vector2::new(ahead.x * cos - ahead.y * sin, ahead.x * sin + ahead.y * cos),
+        Vector2::new(ahead.x * cos + ahead.y * sin, -ahead.x * sin + ahead.y * cos),
+    ]
+}
+
+// Out along the normals of the walls the feelers hit, pushing harder the nearer the wall.
+pub fn avoidance(hits: &[RayHit]) -> Vector2 {
+    let mut force = Vector2::zeros();
+    for hit in hits {
+        force += (1.0 - hit.get_time()) * hit.get_normal();
+    }
+    truncate(force, 1.0)
+}
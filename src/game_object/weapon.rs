@@ -0,0 +1,271 @@
+use std::f32;
+use ggez::graphics::{Point2, Vector2};
+use ggez::graphics::Color;
+use rand::Rng;
+use super::bullet::Bullet;
+use super::event::Event;
+use super::ObjectID;
+use config::{WeaponConfig, WeaponEffect};
+
+// Something that fires projectiles, and keeps track of its own cooldown and ammo. Whoever holds it
+// steps it and picks the aim, colour and whitelist.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Weapon {
+    config: WeaponConfig,
+    time_since_shot: f32,
+    // Rounds left in the magazine.
+    loaded: u32,
+    // Spare rounds, or None for as many as it takes.
+    reserve: Option<u32>,
+    // Time left until the reload in progress finishes, if any.
+    reload_timer: Option<f32>,
+}
+
+impl Weapon {
+    pub fn new(config: &WeaponConfig) -> Self {
+        Self {
+            config: config.clone(),
+            time_since_shot: 300.0,
+            loaded: config.magazine,
+            reserve: config.reserve,
+            reload_timer: None,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.config.name
+    }
+
+    pub fn get_speed(&self) -> f32 {
+        self.config.speed
+    }
+
+    // None if the weapon never needs reloading.
+    pub fn get_loaded(&self) -> Option<u32> {
+        if self.config.magazine > 0 {
+            Some(self.loaded)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_reserve(&self) -> Option<u32> {
+        self.reserve
+    }
+
+    pub fn is_reloading(&self) -> bool {
+        self.reload_timer.is_some()
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        if self.time_since_shot < 200.0 {
+            self.time_since_shot += dt;
+        }
+        if let Some(timer) = self.reload_timer {
+            if timer - dt > 0.0 {
+                self.reload_timer = Some(timer - dt);
+            } else {
+                self.finish_reload();
+            }
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.time_since_shot >= self.config.fire_rate && !self.is_reloading()
+            && (self.config.magazine == 0 || self.loaded > 0)
+    }
+
+    // Start reloading, unless the weapon is already reloading, has a full magazine or has nothing
+    // to reload from.
+    pub fn reload(&mut self) {
+        if self.is_reloading() || self.loaded >= self.config.magazine || self.reserve == Some(0) {
+            return;
+        }
+        self.reload_timer = Some(self.config.reload_time);
+    }
+
+    // Stop a reload in progress without refilling anything, like when the weapon is put away.
+    pub fn cancel_reload(&mut self) {
+        self.reload_timer = None;
+    }
+
+    fn finish_reload(&mut self) {
+        self.reload_timer = None;
+        let wanted = self.config.magazine - self.loaded;
+        let taken = match self.reserve {
+            Some(reserve) => {
+                let taken = wanted.min(reserve);
+                self.reserve = Some(reserve - taken);
+                taken
+            }
+            None => wanted,
+        };
+        self.loaded += taken;
+    }
+
+    // Fire from origin towards direction if the weapon is ready, using up a round. Each pellet
+    // strays by a random angle within the spread, so randomness has to come from the World to keep
+    // replays working. An empty magazine starts a reload.
+    pub fn fire<R: Rng>(
+        &mut self,
+        origin: Point2,
+        direction: Vector2,
+        color: Color,
+        whitelist: &[ObjectID],
+        rng: &mut R,
+    ) -> Vec<Bullet> {
+        if self.config.magazine > 0 && self.loaded == 0 {
+            self.reload();
+        }
+        if !self.is_ready() || direction == Vector2::zeros() {
+            return Vec::new();
+        }
+        self.time_since_shot = 0.0;
+        if self.config.magazine > 0 {
+            self.loaded -= 1;
+            if self.loaded == 0 {
+                self.reload();
+            }
+        }
+        let aim = direction.normalize();
+        let half_spread = self.config.spread.to_radians() / 2.0;
+        let mut bullets = Vec::new();
+        for _ in 0..self.config.pellets {
+            let angle = if half_spread > 0.0 {
+                rng.gen_range(-half_spread, half_spread)
+            } else {
+                0.0
+            };
+            let (sin, cos) = angle.sin_cos();
+            let heading = Vector2::new(aim.x * cos - aim.y * sin, aim.x * sin + aim.y * cos);
+            bullets.push(
+                Bullet::new(
+                    origin,
+                    self.config.speed * heading,
+                    color,
+                    whitelist.to_vec(),
+                    self.get_effects(heading),
+                ).with_lifetime(self.config.lifetime),
+            );
+        }
+        bullets
+    }
+
+    // The events a projectile flying along heading sets off in what it hits.
+    fn get_effects(&self, heading: Vector2) -> Vec<Event> {
+        self.config
+            .effects
+            .iter()
+            .map(|effect| match *effect {
+                WeaponEffect::Damage(damage) => Event::Damage(damage),
+                WeaponEffect::Knockback(magnitude) => Event::Impulse(magnitude * heading),
+                WeaponEffect::Status(status) => Event::Status(status),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::SavableRng;
+    use super::*;
+
+    // Three rounds to a magazine, and four more to reload from.
+    fn rifle() -> Weapon {
+        Weapon::new(&WeaponConfig {
+            fire_rate: 0.1,
+            magazine: 3,
+            reserve: Some(4),
+            reload_time: 1.0,
+            ..WeaponConfig::default()
+        })
+    }
+
+    // Fires straight away. Returns how many bullets came out.
+    fn shoot(weapon: &mut Weapon) -> usize {
+        let color = Color::new(1.0, 1.0, 1.0, 1.0);
+        let (origin, direction) = (Point2::new(0.0, 0.0), Vector2::new(1.0, 0.0));
+        let mut rng = SavableRng::new(1);
+        weapon.fire(origin, direction, color, &[], &mut rng).len()
+    }
+
+    // Waits for the cooldown, then fires.
+    fn fire(weapon: &mut Weapon) -> usize {
+        weapon.step(0.1);
+        shoot(weapon)
+    }
+
+    #[test]
+    fn firing_uses_up_the_magazine() {
+        let mut weapon = rifle();
+        assert_eq!(weapon.get_loaded(), Some(3));
+        assert_eq!(fire(&mut weapon), 1);
+        assert_eq!(weapon.get_loaded(), Some(2));
+        //Still cooling down.
+        assert_eq!(shoot(&mut weapon), 0);
+        assert_eq!(weapon.get_loaded(), Some(2));
+        assert_eq!(fire(&mut weapon), 1);
+        assert_eq!(weapon.get_loaded(), Some(1));
+        assert_eq!(weapon.get_reserve(), Some(4));
+    }
+
+    #[test]
+    fn an_empty_magazine_reloads_from_the_reserve() {
+        let mut weapon = rifle();
+        for _ in 0..3 {
+            assert_eq!(fire(&mut weapon), 1);
+        }
+        assert_eq!(weapon.get_loaded(), Some(0));
+        assert!(weapon.is_reloading());
+        weapon.step(0.95);
+        assert_eq!(weapon.get_loaded(), Some(0));
+        //The reload finishes on this step, taking a full magazine out of the reserve.
+        assert_eq!(fire(&mut weapon), 1);
+        assert_eq!(weapon.get_loaded(), Some(2));
+        assert_eq!(weapon.get_reserve(), Some(1));
+
+        //Only one round left to top up with, then nothing at all.
+        for _ in 0..2 {
+            assert_eq!(fire(&mut weapon), 1);
+        }
+        weapon.step(1.0);
+        assert_eq!(weapon.get_loaded(), Some(1));
+        assert_eq!(weapon.get_reserve(), Some(0));
+        assert_eq!(fire(&mut weapon), 1);
+        assert!(!weapon.is_reloading());
+        assert_eq!(fire(&mut weapon), 0);
+        assert_eq!(weapon.get_loaded(), Some(0));
+    }
+
+    #[test]
+    fn no_firing_while_reloading() {
+        let mut weapon = rifle();
+        assert_eq!(fire(&mut weapon), 1);
+        weapon.reload();
+        assert!(weapon.is_reloading());
+        for _ in 0..5 {
+            assert_eq!(fire(&mut weapon), 0);
+        }
+        assert_eq!(weapon.get_loaded(), Some(2));
+        weapon.step(0.5);
+        assert!(!weapon.is_reloading());
+        assert_eq!(weapon.get_loaded(), Some(3));
+        assert_eq!(weapon.get_reserve(), Some(3));
+        //Nothing to do with a full magazine.
+        weapon.reload();
+        assert!(!weapon.is_reloading());
+    }
+
+    #[test]
+    fn no_magazine_never_reloads() {
+        let mut weapon = Weapon::new(&WeaponConfig {
+            magazine: 0,
+            ..WeaponConfig::default()
+        });
+        for _ in 0..50 {
+            assert_eq!(fire(&mut weapon), 1);
+            assert!(!weapon.is_reloading());
+        }
+        assert_eq!(weapon.get_loaded(), None);
+    }
+}
@@ -1,29 +1,74 @@
 extern crate ggez;
 extern crate rand;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate toml;
 
 use ggez::conf;
 use ggez::event::*;
 use ggez::ContextBuilder;
+use std::env;
+use std::path::PathBuf;
+use world::replay::Replay;
 
 mod game_object;
 mod assets;
+mod config;
 mod main_state;
 mod utils;
+mod world;
 
 fn main() {
-    let (width, height) = (800, 450);
+    let config = match config::Config::load("config.toml") {
+        Ok(config) => config,
+        Err(e) => {
+            println!("Could not load config!");
+            println!("Error: {}", e);
+            return;
+        }
+    };
+    let window = &config.window;
     let cb = ContextBuilder::new("gift", "ggez")
-        .window_setup(conf::WindowSetup::default().title("WINDOW_TITLE"))
-        .window_mode(conf::WindowMode::default().dimensions(width, height));
+        .window_setup(conf::WindowSetup::default().title(&window.title))
+        .window_mode(
+            conf::WindowMode::default()
+                .dimensions(window.width, window.height)
+                .vsync(window.vsync),
+        );
 
     let ctx = &mut cb.build().unwrap();
-    match main_state::MainState::new(ctx, width, height) {
+    // usage: gift [level file] [--record <replay file>] [--replay <replay file>]
+    let mut level_path = "levels/arena.toml".to_owned();
+    let mut replay_mode = main_state::ReplayMode::Off;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--record" => if let Some(path) = args.next() {
+                replay_mode = main_state::ReplayMode::Record(PathBuf::from(path));
+            },
+            "--replay" => if let Some(path) = args.next() {
+                match Replay::load(&path) {
+                    Ok(replay) => replay_mode = main_state::ReplayMode::Play(replay),
+                    Err(e) => {
+                        println!("Could not load replay!");
+                        println!("Error: {}", e);
+                        return;
+                    }
+                }
+            },
+            _ => level_path = arg,
+        }
+    }
+
+    match main_state::MainState::new(ctx, &config, level_path, replay_mode) {
         Err(e) => {
             println!("Could not load game!");
             println!("Error: {}", e);
         }
         Ok(ref mut game) => {
             let result = run(ctx, game);
+            game.save_recording();
             if let Err(e) = result {
                 println!("Error encountered running game: {}", e);
             } else {
@@ -42,6 +42,20 @@ impl DebugTable {
         }
     }
 
+    // Draws a short piece of text centered just above a point on screen, such as a label over an
+    // object.
+    pub fn draw_label(&mut self, ctx: &mut Context, string: &str, point: Point2) {
+        if !self.images.contains_key(string) {
+            self.images.insert(String::from(string), DebugTable::make_image_from(ctx, string));
+        }
+        let image = &self.images[string];
+        let mut cursor = Point2::new(
+            point.x - image.width() as f32 / 2.0,
+            point.y - image.height() as f32,
+        );
+        DebugTable::draw(ctx, image, &mut cursor, false);
+    }
+
     fn draw_text(&mut self, ctx: &mut Context, string: &str, cursor: &mut Point2, new_line: bool) {
         if let Some(image) = self.images.get(string) {
             DebugTable::draw(ctx, image, cursor, new_line);
@@ -1,9 +1,40 @@
-use ggez::graphics::{Vector2};
+use std::collections::HashMap;
+use ggez::graphics::{Point2, Vector2};
+use ggez::event::{Button, Keycode};
+use ggez::event::Axis as ControllerAxis;
+use config::{BindingsConfig, GamepadConfig};
+use world::PlayerInput;
 
 //enums
-enum Action {
-    Item,
-    None,
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    ShootUp,
+    ShootDown,
+    ShootLeft,
+    ShootRight,
+    UseItem,
+    NextWeapon,
+    SelectWeapon(usize),
+    Reload,
+    Reset,
+    QuickSave,
+    QuickLoad,
+    Quit,
+}
+
+impl Action {
+    // Whether the action lasts for as long as it's held, rather than happening once per press.
+    pub fn is_held(&self) -> bool {
+        match *self {
+            Action::MoveUp | Action::MoveDown | Action::MoveLeft | Action::MoveRight => true,
+            Action::ShootUp | Action::ShootDown | Action::ShootLeft | Action::ShootRight => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -89,19 +120,213 @@ impl DirectionInputStack {
     }
 }
 
+// AnalogStick struct
+pub struct AnalogStick {
+    x: f32,
+    y: f32,
+}
+
+impl AnalogStick {
+    pub fn new() -> Self {
+        Self { x: 0.0, y: 0.0 }
+    }
+
+    fn axis_value(value: i16) -> f32 {
+        (value as f32 / 32767.0).max(-1.0)
+    }
+
+    pub fn set_x(&mut self, value: i16) {
+        self.x = AnalogStick::axis_value(value);
+    }
+
+    //SDL's y axis points down, ours points up.
+    pub fn set_y(&mut self, value: i16) {
+        self.y = -1.0 * AnalogStick::axis_value(value);
+    }
+
+    // The stick direction with the dead zone cut out, rescaled so that the magnitude goes from 0
+    // at the edge of the dead zone to 1 at full tilt.
+    pub fn get_direction(&self, dead_zone: f32) -> Vector2 {
+        let direction = Vector2::new(self.x, self.y);
+        let magnitude = direction.norm();
+        if magnitude <= dead_zone {
+            return Vector2::zeros();
+        }
+        let scaled = ((magnitude - dead_zone) / (1.0 - dead_zone)).min(1.0);
+        direction * (scaled / magnitude)
+    }
+}
+
+// KeyMap struct
+pub struct KeyMap {
+    actions: HashMap<Keycode, Vec<Action>>,
+    button_actions: HashMap<Button, Vec<Action>>,
+}
+
+impl KeyMap {
+    pub fn new(bindings: &BindingsConfig) -> Result<Self, String> {
+        let mut key_map = Self {
+            actions: HashMap::new(),
+            button_actions: HashMap::new(),
+        };
+        key_map.bind_button(Action::MoveUp, Button::DPadUp);
+        key_map.bind_button(Action::MoveDown, Button::DPadDown);
+        key_map.bind_button(Action::MoveLeft, Button::DPadLeft);
+        key_map.bind_button(Action::MoveRight, Button::DPadRight);
+        key_map.bind_button(Action::UseItem, Button::A);
+        key_map.bind_button(Action::NextWeapon, Button::RightShoulder);
+        key_map.bind_button(Action::Reload, Button::X);
+        key_map.bind_button(Action::Reset, Button::Start);
+        key_map.bind_button(Action::Quit, Button::Back);
+        key_map.bind(Action::MoveUp, &bindings.move_up)?;
+        key_map.bind(Action::MoveDown, &bindings.move_down)?;
+        key_map.bind(Action::MoveLeft, &bindings.move_left)?;
+        key_map.bind(Action::MoveRight, &bindings.move_right)?;
+        key_map.bind(Action::ShootUp, &bindings.shoot_up)?;
+        key_map.bind(Action::ShootDown, &bindings.shoot_down)?;
+        key_map.bind(Action::ShootLeft, &bindings.shoot_left)?;
+        key_map.bind(Action::ShootRight, &bindings.shoot_right)?;
+        key_map.bind(Action::UseItem, &bindings.use_item)?;
+        key_map.bind(Action::NextWeapon, &bindings.next_weapon)?;
+        key_map.bind(Action::SelectWeapon(0), &bindings.weapon_1)?;
+        key_map.bind(Action::SelectWeapon(1), &bindings.weapon_2)?;
+        key_map.bind(Action::SelectWeapon(2), &bindings.weapon_3)?;
+        key_map.bind(Action::Reload, &bindings.reload)?;
+        key_map.bind(Action::Reset, &bindings.reset)?;
+        key_map.bind(Action::QuickSave, &bindings.quick_save)?;
+        key_map.bind(Action::QuickLoad, &bindings.quick_load)?;
+        key_map.bind(Action::Quit, &bindings.quit)?;
+        Ok(key_map)
+    }
+
+    fn bind(&mut self, action: Action, key_names: &[String]) -> Result<(), String> {
+        for name in key_names {
+            match Keycode::from_name(name) {
+                Some(keycode) => self.actions
+                    .entry(keycode)
+                    .or_insert_with(Vec::new)
+                    .push(action),
+                None => return Err(format!("unknown key \"{}\" bound to {:?}", name, action)),
+            }
+        }
+        Ok(())
+    }
+
+    fn bind_button(&mut self, action: Action, button: Button) {
+        self.button_actions
+            .entry(button)
+            .or_insert_with(Vec::new)
+            .push(action);
+    }
+
+    pub fn get_actions(&self, keycode: Keycode) -> Vec<Action> {
+        match self.actions.get(&keycode) {
+            Some(actions) => actions.clone(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn get_button_actions(&self, button: Button) -> Vec<Action> {
+        match self.button_actions.get(&button) {
+            Some(actions) => actions.clone(),
+            None => Vec::new(),
+        }
+    }
+}
+
 //Input struct
 pub struct Input {
-    action: Action,
+    pub key_map: KeyMap,
     pub move_stack: DirectionInputStack,
     pub shoot_stack: DirectionInputStack,
+    pub move_stick: AnalogStick,
+    pub shoot_stick: AnalogStick,
+    // Screen coordinates of the cursor.
+    pub mouse_position: Point2,
+    pub mouse_aiming: bool,
+    pub reset_requested: bool,
+    // The weapon to switch to on the next tick.
+    pub weapon_requested: Option<usize>,
+    pub reload_requested: bool,
+    dead_zone: f32,
 }
 
 impl Input {
-    pub fn new() -> Self {
+    pub fn new(key_map: KeyMap, gamepad: &GamepadConfig) -> Self {
         Self {
-            action: Action::None,
+            key_map,
             move_stack: DirectionInputStack::new(),
             shoot_stack: DirectionInputStack::new(),
+            move_stick: AnalogStick::new(),
+            shoot_stick: AnalogStick::new(),
+            mouse_position: Point2::new(0.0, 0.0),
+            mouse_aiming: false,
+            reset_requested: false,
+            weapon_requested: None,
+            reload_requested: false,
+            dead_zone: gamepad.dead_zone.max(0.0).min(0.99),
+        }
+    }
+
+    pub fn set_controller_axis(&mut self, axis: ControllerAxis, value: i16) {
+        match axis {
+            ControllerAxis::LeftX => self.move_stick.set_x(value),
+            ControllerAxis::LeftY => self.move_stick.set_y(value),
+            ControllerAxis::RightX => self.shoot_stick.set_x(value),
+            ControllerAxis::RightY => self.shoot_stick.set_y(value),
+            _ => (),
+        }
+    }
+
+    // The stack, direction and axis that a directional action drives.
+    fn get_direction_input(
+        &mut self,
+        action: Action,
+    ) -> Option<(&mut DirectionInputStack, DirectionInputScalar, Axis)> {
+        use self::DirectionInputScalar::*;
+        match action {
+            Action::MoveUp => Some((&mut self.move_stack, Positive, Axis::Y)),
+            Action::MoveDown => Some((&mut self.move_stack, Negative, Axis::Y)),
+            Action::MoveLeft => Some((&mut self.move_stack, Negative, Axis::X)),
+            Action::MoveRight => Some((&mut self.move_stack, Positive, Axis::X)),
+            Action::ShootUp => Some((&mut self.shoot_stack, Positive, Axis::Y)),
+            Action::ShootDown => Some((&mut self.shoot_stack, Negative, Axis::Y)),
+            Action::ShootLeft => Some((&mut self.shoot_stack, Negative, Axis::X)),
+            Action::ShootRight => Some((&mut self.shoot_stack, Positive, Axis::X)),
+            _ => None,
+        }
+    }
+
+    pub fn activate(&mut self, action: Action) {
+        if let Some((stack, direction, axis)) = self.get_direction_input(action) {
+            stack.activate_direction(direction, axis);
+        }
+    }
+
+    pub fn deactivate(&mut self, action: Action) {
+        if let Some((stack, direction, axis)) = self.get_direction_input(action) {
+            stack.deactivate_direction(direction, axis);
+        }
+    }
+    // The sticks take priority over the keyboard while they are pushed past the dead zone.
+    pub fn snapshot(&self) -> PlayerInput {
+        let mut input = PlayerInput {
+            movement: self.move_stack.get_direction_recent(),
+            shoot_direction: self.shoot_stack.get_direction_recent(),
+            shooting: self.shoot_stack.is_active(),
+            switch_weapon: self.weapon_requested,
+            reload: self.reload_requested,
+            reset: self.reset_requested,
+        };
+        let stick_movement = self.move_stick.get_direction(self.dead_zone);
+        if stick_movement != Vector2::zeros() {
+            input.movement = stick_movement;
+        }
+        let stick_aim = self.shoot_stick.get_direction(self.dead_zone);
+        if stick_aim != Vector2::zeros() {
+            input.shoot_direction = stick_aim;
+            input.shooting = true;
         }
+        input
     }
 }
@@ -1,149 +1,233 @@
-use ggez::{Context, GameResult};
+use std::path::PathBuf;
+use ggez::{Context, GameError, GameResult};
 use ggez::graphics::{Point2, Vector2};
-use ggez::graphics::Image;
 use ggez::graphics;
 use ggez::event::*;
+use ggez::event::Axis as ControllerAxis;
 use ggez::timer;
-use assets::Assets;
-use game_object;
+use assets::{Assets, DrawableAsset};
+use config::Config;
 use game_object::*;
-use game_object::block::Block;
-use game_object::mob::*;
-use game_object::bullet::Bullet;
-use utils::get_all;
+use game_object::behaviour::HasBehaviour;
+use game_object::collision::Hitbox;
+use world::{PlayerInput, World};
+use world::level::Level;
+use world::replay::{Replay, ReplayPlayback};
+use rand;
 
 mod debug;
 mod input;
 
+const QUICKSAVE_PATH: &str = "quicksave.toml";
+
 use self::input::*;
-use self::input::Axis;
+
+pub enum ReplayMode {
+    Off,
+    Record(PathBuf),
+    Play(Replay),
+}
+
+// Where a point in the world is drawn, for a camera centered on camera. Screen y points down and
+// world y points up.
+pub fn world_to_screen_coords(point: Point2, camera: Vector2, screen_size: Vector2) -> Point2 {
+    let x = point.x + -1.0 * camera.x + screen_size.x / 2.0;
+    let y = screen_size.y - (point.y + -1.0 * camera.y + screen_size.y / 2.0);
+    Point2::new(x, y)
+}
+
+// The point in the world drawn at a point on screen, the inverse of world_to_screen_coords.
+pub fn screen_to_world_coords(point: Point2, camera: Vector2, screen_size: Vector2) -> Point2 {
+    let x = point.x + camera.x - screen_size.x / 2.0;
+    let y = screen_size.y / 2.0 - point.y + camera.y;
+    Point2::new(x, y)
+}
+
 pub struct MainState {
     screen_w: u32,
     screen_h: u32,
+    tick_rate: u32,
     input: Input,
     assets: Assets,
-    player_mob: player::Player,
-    //  player_gun:
-    mobs: Vec<Dummy>,
-    blocks: Vec<Block>,
-    projectiles: Vec<Bullet>,
+    world: World,
+    // Where the level came from, or None when it came from a replay.
+    level_path: Option<PathBuf>,
+    recording: Option<(PathBuf, Replay)>,
+    playback: Option<ReplayPlayback>,
     camera: Vector2,
     debug_display: debug::DebugTable,
+    show_mob_states: bool,
 }
 
 impl MainState {
-
-    fn get_renderable(&mut self) -> Vec<&mut Renderable> {
-        let mut return_vec: Vec<&mut Renderable> = Vec::new();
-        return_vec.push(&mut self.player_mob);
-        for r in get_all(&mut self.blocks) {
-            return_vec.push(r);
-        }
-        for r in get_all(&mut self.mobs) {
-            return_vec.push(r);
-        }
-        for r in get_all(&mut self.projectiles) {
-            return_vec.push(r);
-        }
-        return_vec
-    }
-
-    pub fn new(ctx: &mut Context, screen_w: u32, screen_h: u32) -> GameResult<MainState> {
-        let mut state = MainState {
-            screen_w,
-            screen_h,
-            input: Input::new(),
+    pub fn new<P: Into<PathBuf>>(
+        ctx: &mut Context,
+        config: &Config,
+        level_path: P,
+        replay_mode: ReplayMode,
+    ) -> GameResult<MainState> {
+        let level_path = level_path.into();
+        let key_map = match KeyMap::new(&config.bindings) {
+            Ok(key_map) => key_map,
+            Err(e) => return Err(GameError::ResourceLoadError(format!("config.toml: {}", e))),
+        };
+        let (world, tick_rate, recording, playback, level_path) = match replay_mode {
+            ReplayMode::Play(replay) => {
+                let world = World::new(replay.level.clone(), replay.gameplay.clone(), replay.seed);
+                (world, replay.tick_rate, None, Some(ReplayPlayback::new(replay)), None)
+            }
+            replay_mode => {
+                let level = match Level::load(&level_path) {
+                    Ok(level) => level,
+                    Err(e) => {
+                        return Err(GameError::ResourceLoadError(format!(
+                            "{}: {}",
+                            level_path.display(),
+                            e
+                        )))
+                    }
+                };
+                let seed = rand::random::<u32>();
+                let recording = match replay_mode {
+                    ReplayMode::Record(path) => {
+                        let replay =
+                            Replay::new(seed, config.tick_rate, level.clone(), config.gameplay.clone());
+                        Some((path, replay))
+                    }
+                    _ => None,
+                };
+                let world = World::new(level, config.gameplay.clone(), seed);
+                (world, config.tick_rate, recording, None, Some(level_path))
+            }
+        };
+        let state = MainState {
+            screen_w: config.window.width,
+            screen_h: config.window.height,
+            tick_rate,
+            input: Input::new(key_map, &config.gamepad),
             assets: Assets::new(ctx),
-            player_mob: player::Player::new(),
-            mobs: Vec::new(),
-            blocks: Vec::new(),
-            projectiles: Vec::new(),
+            world,
+            level_path,
+            recording,
+            playback,
             camera: Vector2::new(0.0, 0.0),
             debug_display: debug::DebugTable::new(ctx, Point2::new(0.0, 0.0)),
+            show_mob_states: config.debug.show_mob_states,
         };
-        state.blocks.push(Block::wallh(-170.0, 200.0));
-        state.blocks.push(Block::wallh(-190.0, -200.0));
-        state.blocks.push(Block::wallv(210.0, -200.0));
-        state.blocks.push(Block::wallv(-190.0, -180.0));
         Ok(state)
     }
 
-    fn handle_player_input(&mut self) {
-        self.player_mob
-            .set_movement(self.input.move_stack.get_direction_recent());
-        self.player_mob
-            .set_shoot_direction(self.input.shoot_stack.get_direction_recent());
-        if self.input.shoot_stack.is_active() {
-            match self.player_mob.shoot() {
-                Some(projectile) => self.projectiles.push(projectile),
-                None => (),
+    // Write out the recording, if there is one. Called once the game loop has exited.
+    pub fn save_recording(&self) {
+        if let Some((ref path, ref replay)) = self.recording {
+            match replay.save(path) {
+                Ok(()) => println!("Replay saved to {}", path.display()),
+                Err(e) => println!("Could not save replay to {}: {}", path.display(), e),
             }
         }
     }
 
-    fn calculate_step(&mut self, dt: f32) {
-        self.player_mob.step(dt);
-        for object in &mut self.mobs {
-            object.step(dt);
-        }
-        for object in &mut self.blocks {
-            object.step(dt);
-        }
-        for object in &mut self.projectiles {
-            object.step(dt);
+    fn action_down(&mut self, ctx: &mut Context, action: Action) {
+        match action {
+            Action::Reset => self.input.reset_requested = true,
+            Action::QuickSave => self.quick_save(),
+            Action::QuickLoad => self.quick_load(),
+            Action::Quit => ctx.quit().unwrap(),
+            Action::UseItem => (), // No items yet
+            Action::NextWeapon => {
+                let player = self.world.get_player();
+                let current = self.input.weapon_requested.unwrap_or(player.get_weapon_index());
+                if player.get_weapon_count() > 0 {
+                    self.input.weapon_requested = Some((current + 1) % player.get_weapon_count());
+                }
+            }
+            Action::SelectWeapon(index) => self.input.weapon_requested = Some(index),
+            Action::Reload => self.input.reload_requested = true,
+            _ => self.input.activate(action),
         }
-        //load player hp to debug display
-        self.debug_display.load("hp".to_owned(), self.player_mob.get_health().to_string());
     }
 
-    fn clear_objects(&mut self) {
-        self.projectiles
-            .retain(|ref projectile| !projectile.should_delete());
-        self.mobs.retain(|ref mob| !mob.should_delete());
-        if self.player_mob.should_delete() {
-            self.player_mob = player::Player::new();
-            self.mobs.drain(..);
-            self.projectiles.drain(..);
+    fn quick_save(&self) {
+        match self.world.save_snapshot(QUICKSAVE_PATH) {
+            Ok(()) => println!("Saved to {}", QUICKSAVE_PATH),
+            Err(e) => println!("Could not save to {}: {}", QUICKSAVE_PATH, e),
         }
     }
 
-    fn reset(&mut self) {
-        self.player_mob = player::Player::new();
-        self.mobs.drain(..);
-        self.projectiles.drain(..);
-        self.mobs.push(Dummy::new(Point2::new(100.0, 100.0)));
-        self.mobs.push(Dummy::new(Point2::new(100.0, -100.0)));
-        self.mobs.push(Dummy::new(Point2::new(-100.0, -100.0)));
-        self.mobs.push(Dummy::new(Point2::new(150.0, 150.0)));
-        self.mobs.push(Dummy::new(Point2::new(150.0, -150.0)));
-        self.mobs.push(Dummy::new(Point2::new(-150.0, -150.0)));
+    // Replays only hold inputs, so swapping the world out from under one would break it.
+    fn quick_load(&mut self) {
+        if self.recording.is_some() || self.playback.is_some() {
+            println!("Can't quick load while recording or playing a replay.");
+            return;
+        }
+        match World::load_snapshot(QUICKSAVE_PATH) {
+            Ok(world) => {
+                self.world = world;
+                self.update_camera();
+            }
+            Err(e) => println!("Could not load {}: {}", QUICKSAVE_PATH, e),
+        }
     }
 
-    fn calculate_ai(&mut self) {
-        for object in &mut self.mobs {
-            object.set_target(self.player_mob.get_position());
+    // Reload the level from disk so edits show up without a restart. Skipped while recording,
+    // since the replay only stores the level it started with, and once a replay has finished, so
+    // that resetting goes back to the replay's own level.
+    fn reload_level(&mut self) {
+        if self.recording.is_some() {
+            return;
+        }
+        if let Some(ref level_path) = self.level_path {
+            match Level::load(level_path) {
+                Ok(level) => self.world.load_level(level),
+                Err(e) => println!("Could not reload {}: {}", level_path.display(), e),
+            }
         }
     }
 
-    fn calculate_physics(&mut self, dt: f32) {
-        collision::vec_vec_physics(dt, &mut self.mobs, &mut self.blocks);
-        collision::vec_physics(dt, &mut self.mobs);
-        collision::object_vec_physics(dt, &mut self.player_mob, &mut self.blocks);
-        collision::object_vec_physics(dt, &mut self.player_mob, &mut self.mobs);
+    // The input for the next tick, either from the replay being played or from the player.
+    fn next_player_input(&mut self) -> PlayerInput {
+        let replayed = match self.playback {
+            Some(ref mut playback) => playback.next_input(),
+            None => None,
+        };
+        if let Some(player_input) = replayed {
+            return player_input;
+        }
+        if self.playback.is_some() {
+            println!("Replay finished.");
+            self.playback = None;
+        }
+
+        let player_input = self.get_player_input();
+        self.input.reset_requested = false;
+        self.input.weapon_requested = None;
+        self.input.reload_requested = false;
+        if player_input.reset {
+            self.reload_level();
+        }
+        if let Some((_, ref mut replay)) = self.recording {
+            replay.record(&player_input);
+        }
+        player_input
     }
 
-    fn calculate_collision_events(&mut self, dt: f32) {
-        game_object::vec_vec_collision_events(dt, &mut self.mobs, &mut self.projectiles);
-        game_object::object_vec_collision_events(dt, &mut self.player_mob, &mut self.projectiles);
-        game_object::object_vec_collision_events(dt, &mut self.player_mob, &mut self.mobs);
+    fn get_screen_size(&self) -> Vector2 {
+        Vector2::new(self.screen_w as f32, self.screen_h as f32)
     }
 
-    fn world_to_screen_coords(&self, point: Point2) -> Point2 {
-        let width = self.screen_w as f32;
-        let height = self.screen_h as f32;
-        let x = point.x + -1.0 * self.camera.x + width / 2.0;
-        let y = height - (point.y + -1.0 * self.camera.y + height / 2.0);
-        Point2::new(x, y)
+    // Holding the mouse button overrides any other aim and fires at the cursor.
+    fn get_player_input(&self) -> PlayerInput {
+        let mut player_input = self.input.snapshot();
+        if self.input.mouse_aiming {
+            let (camera, screen_size) = (self.camera, self.get_screen_size());
+            let target = screen_to_world_coords(self.input.mouse_position, camera, screen_size);
+            let direction = target - self.world.get_player().get_center_position();
+            if direction != Vector2::zeros() {
+                player_input.shoot_direction = direction;
+                player_input.shooting = true;
+            }
+        }
+        player_input
     }
 
     fn _is_on_screen(&self, point: Point2) -> bool {
@@ -153,16 +237,44 @@ impl MainState {
 
     fn update_camera(&mut self) {
         let (x, y) = (
-            self.player_mob.get_position().x,
-            self.player_mob.get_position().y,
+            self.world.get_player().get_position().x,
+            self.world.get_player().get_position().y,
         );
         self.camera.x = x;
         self.camera.y = y;
     }
 
+    // Make sure every polygon block has its mesh, since they can change with each level load.
+    fn load_polygons(&mut self, ctx: &mut Context) {
+        for block in self.world.get_blocks() {
+            if let (DrawableAsset::Polygon(index), &Hitbox::Polygon(ref points)) =
+                (block.get_drawable_asset(), block.get_hitbox())
+            {
+                self.assets.load_polygon(ctx, index, points);
+            }
+        }
+    }
+
+    //Label each mob with its behaviour state and seconds spent in it, just above its top edge.
+    fn draw_mob_states(&mut self, ctx: &mut Context) {
+        let mut labels = Vec::new();
+        for mob in self.world.get_mobs() {
+            let top = mob.get_center_position() + Vector2::new(0.0, mob.get_hitbox().vec().y / 2.0);
+            let behaviour = mob.get_behaviour();
+            let (state, time) = (behaviour.get_state(), behaviour.get_time_in_state());
+            let state = format!("{:?} {:.1}s", state, time);
+            let point = world_to_screen_coords(top, self.camera, self.get_screen_size());
+            labels.push((state, point));
+        }
+        for (state, point) in labels {
+            self.debug_display.draw_label(ctx, &state, point);
+        }
+    }
+
     fn draw_object<T: Renderable>(&self, ctx: &mut Context, object: &T) -> GameResult<()> {
         //Find the pixel position on screen of the object.
-        let pos = self.world_to_screen_coords(object.get_position());
+        let (camera, screen_size) = (self.camera, self.get_screen_size());
+        let pos = world_to_screen_coords(object.get_position(), camera, screen_size);
 
         //      //If the object is not on screen, do nothing.
         //      if !self.is_on_screen(pos) {
@@ -179,6 +291,7 @@ impl MainState {
         let drawparams = graphics::DrawParam {
             dest: pos,
             color: object.get_color(),
+            scale: object.get_scale(),
             ..Default::default()
         };
 
@@ -189,17 +302,31 @@ impl MainState {
 
 impl EventHandler for MainState {
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        const DESIRED_FPS: u32 = 60;
-        while timer::check_update_time(ctx, DESIRED_FPS) {
-            let seconds = 1.0 / (DESIRED_FPS as f32);
-            self.handle_player_input();
-            self.calculate_step(seconds);
-            self.calculate_collision_events(seconds);
-            self.calculate_physics(seconds);
-            self.calculate_ai();
-            self.clear_objects();
+        while timer::check_update_time(ctx, self.tick_rate) {
+            let seconds = 1.0 / (self.tick_rate as f32);
+            let player_input = self.next_player_input();
+            self.world.update(seconds, &player_input);
             self.update_camera();
         }
+        //load player hp to debug display
+        let hp = self.world.get_player().get_health();
+        self.debug_display.load("hp".to_owned(), hp.to_string());
+        if let Some(weapon) = self.world.get_player().get_weapon() {
+            self.debug_display.load("weapon".to_owned(), weapon.get_name().to_owned());
+            // Rounds in the magazine over spare rounds, with no reserve shown when it's unlimited.
+            let ammo = match (weapon.get_loaded(), weapon.get_reserve()) {
+                _ if weapon.is_reloading() => "reloading".to_owned(),
+                (Some(loaded), Some(reserve)) => format!("{}/{}", loaded, reserve),
+                (Some(loaded), None) => loaded.to_string(),
+                (None, _) => "-".to_owned(),
+            };
+            self.debug_display.load("ammo".to_owned(), ammo);
+        }
+        let statuses = self.world.get_player().get_statuses().describe();
+        self.debug_display.load("status".to_owned(), statuses);
+        if let Some(wave) = self.world.get_wave() {
+            self.debug_display.load("wave".to_owned(), wave.to_string());
+        }
         Ok(())
     }
 
@@ -214,23 +341,27 @@ impl EventHandler for MainState {
         //draw the debug table
         self.debug_display.render(ctx);
         //draw the player
-        if let Err(error) = self.draw_object(ctx, &self.player_mob) {
+        if let Err(error) = self.draw_object(ctx, self.world.get_player()) {
             return Err(error);
         }
         //draw objects with renderable component
-        for object in &self.mobs {
+        for object in self.world.get_mobs() {
             if let Err(error) = self.draw_object(ctx, object) {
                 return Err(error);
             }
         }
+        if self.show_mob_states {
+            self.draw_mob_states(ctx);
+        }
 
-        for object in &self.blocks {
+        self.load_polygons(ctx);
+        for object in self.world.get_blocks() {
             if let Err(error) = self.draw_object(ctx, object) {
                 return Err(error);
             }
         }
 
-        for object in &self.projectiles {
+        for object in self.world.get_projectiles() {
             if let Err(error) = self.draw_object(ctx, object) {
                 return Err(error);
             }
@@ -244,99 +375,88 @@ impl EventHandler for MainState {
         Ok(())
     }
 
-    fn key_down_event(&mut self, ctx: &mut Context, keycode: Keycode, _keymod: Mod, _repeat: bool) {
-        match keycode {
-            Keycode::R => {
-                self.reset();
-            }
-            Keycode::W => {
-                self.input
-                    .move_stack
-                    .activate_direction(DirectionInputScalar::Positive, Axis::Y);
-            }
-            Keycode::S => {
-                self.input
-                    .move_stack
-                    .activate_direction(DirectionInputScalar::Negative, Axis::Y);
-            }
-            Keycode::A => {
-                self.input
-                    .move_stack
-                    .activate_direction(DirectionInputScalar::Negative, Axis::X);
-            }
-            Keycode::D => {
-                self.input
-                    .move_stack
-                    .activate_direction(DirectionInputScalar::Positive, Axis::X);
-            }
-            Keycode::Up => {
-                self.input
-                    .shoot_stack
-                    .activate_direction(DirectionInputScalar::Positive, Axis::Y);
-            }
-            Keycode::Down => {
-                self.input
-                    .shoot_stack
-                    .activate_direction(DirectionInputScalar::Negative, Axis::Y);
+    //A held key repeats, which mustn't switch weapons or quick save over and over.
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: Keycode, _keymod: Mod, repeat: bool) {
+        for action in self.input.key_map.get_actions(keycode) {
+            if repeat && !action.is_held() {
+                continue;
             }
-            Keycode::Left => {
-                self.input
-                    .shoot_stack
-                    .activate_direction(DirectionInputScalar::Negative, Axis::X);
-            }
-            Keycode::Right => {
-                self.input
-                    .shoot_stack
-                    .activate_direction(DirectionInputScalar::Positive, Axis::X);
-            }
-            Keycode::Escape => ctx.quit().unwrap(),
-            _ => (), // Do nothing
+            self.action_down(ctx, action);
         }
     }
 
     fn key_up_event(&mut self, _ctx: &mut Context, keycode: Keycode, _keymod: Mod, _repeat: bool) {
-        match keycode {
-            Keycode::W => {
-                self.input
-                    .move_stack
-                    .deactivate_direction(DirectionInputScalar::Positive, Axis::Y);
-            }
-            Keycode::S => {
-                self.input
-                    .move_stack
-                    .deactivate_direction(DirectionInputScalar::Negative, Axis::Y);
-            }
-            Keycode::A => {
-                self.input
-                    .move_stack
-                    .deactivate_direction(DirectionInputScalar::Negative, Axis::X);
-            }
-            Keycode::D => {
-                self.input
-                    .move_stack
-                    .deactivate_direction(DirectionInputScalar::Positive, Axis::X);
-            }
-            Keycode::Up => {
-                self.input
-                    .shoot_stack
-                    .deactivate_direction(DirectionInputScalar::Positive, Axis::Y);
-            }
-            Keycode::Down => {
-                self.input
-                    .shoot_stack
-                    .deactivate_direction(DirectionInputScalar::Negative, Axis::Y);
-            }
-            Keycode::Left => {
-                self.input
-                    .shoot_stack
-                    .deactivate_direction(DirectionInputScalar::Negative, Axis::X);
-            }
-            Keycode::Right => {
-                self.input
-                    .shoot_stack
-                    .deactivate_direction(DirectionInputScalar::Positive, Axis::X);
-            }
-            _ => (), // Do nothing
+        for action in self.input.key_map.get_actions(keycode) {
+            self.input.deactivate(action);
+        }
+    }
+
+    fn controller_button_down_event(&mut self, ctx: &mut Context, btn: Button, _instance_id: i32) {
+        for action in self.input.key_map.get_button_actions(btn) {
+            self.action_down(ctx, action);
+        }
+    }
+
+    fn controller_button_up_event(&mut self, _ctx: &mut Context, btn: Button, _instance_id: i32) {
+        for action in self.input.key_map.get_button_actions(btn) {
+            self.input.deactivate(action);
+        }
+    }
+
+    fn controller_axis_event(
+        &mut self,
+        _ctx: &mut Context,
+        axis: ControllerAxis,
+        value: i16,
+        _instance_id: i32,
+    ) {
+        self.input.set_controller_axis(axis, value);
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: i32, y: i32) {
+        if button == MouseButton::Left {
+            self.input.mouse_position = Point2::new(x as f32, y as f32);
+            self.input.mouse_aiming = true;
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: i32, _y: i32) {
+        if button == MouseButton::Left {
+            self.input.mouse_aiming = false;
+        }
+    }
+
+    fn mouse_motion_event(
+        &mut self,
+        _ctx: &mut Context,
+        _state: MouseState,
+        x: i32,
+        y: i32,
+        _xrel: i32,
+        _yrel: i32,
+    ) {
+        self.input.mouse_position = Point2::new(x as f32, y as f32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_coords_round_trip() {
+        let (camera, screen_size) = (Vector2::new(120.0, -35.0), Vector2::new(800.0, 450.0));
+        //The camera is drawn in the middle of the screen, with world y going up.
+        let middle = world_to_screen_coords(Point2::new(120.0, -35.0), camera, screen_size);
+        assert_eq!(middle, Point2::new(400.0, 225.0));
+        let above = world_to_screen_coords(Point2::new(120.0, -25.0), camera, screen_size);
+        assert_eq!(above, Point2::new(400.0, 215.0));
+        for &(x, y) in &[(0.0, 0.0), (-310.5, 42.25), (1000.0, -800.0)] {
+            let point = Point2::new(x, y);
+            let screen = world_to_screen_coords(point, camera, screen_size);
+            assert_eq!(screen_to_world_coords(screen, camera, screen_size), point);
+            let world = screen_to_world_coords(point, camera, screen_size);
+            assert_eq!(world_to_screen_coords(world, camera, screen_size), point);
         }
     }
 }
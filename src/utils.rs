@@ -1,40 +1,188 @@
 use std::error::Error;
 use std::fmt;
+use std::io;
+use rand::Rng;
+use toml;
 
+// Why a config, level, replay or snapshot file couldn't be loaded or saved. Callers say which file.
 #[derive(Debug)]
-pub struct IndexError;
-impl fmt::Display for IndexError {
+pub enum FileError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    // The file parsed, but holds something the game can't use.
+    Invalid(String),
+}
+
+impl fmt::Display for FileError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "index error")
+        match *self {
+            FileError::Io(ref e) => write!(f, "could not access file: {}", e),
+            FileError::Parse(ref e) => write!(f, "could not parse file: {}", e),
+            FileError::Serialize(ref e) => write!(f, "could not write file: {}", e),
+            FileError::Invalid(ref reason) => write!(f, "{}", reason),
+        }
     }
 }
 
-impl Error for IndexError {
+impl Error for FileError {
     fn description(&self) -> &str {
-        "invalid indicies were used"
+        "the file could not be loaded or saved"
     }
 }
 
-pub fn get_two<T>(data: &mut Vec<T>, i: usize, j: usize) -> Result<(&mut T, &mut T), IndexError> {
-    if i == j  || i >= data.len() || j >= data.len() {
-        return Err(IndexError)
+impl From<io::Error> for FileError {
+    fn from(e: io::Error) -> Self {
+        FileError::Io(e)
     }
-    assert!(i != j);
-    assert!(i < data.len());
-    assert!(j < data.len());
-    unsafe {
-        let a = &mut *(data.get_unchecked_mut(i) as *mut T);
-        let b = &mut *(data.get_unchecked_mut(j) as *mut T);
-        Ok((a, b))
+}
+
+impl From<toml::de::Error> for FileError {
+    fn from(e: toml::de::Error) -> Self {
+        FileError::Parse(e)
     }
 }
 
-pub fn get_all<T>(data: &mut Vec<T>) -> Vec<&mut T> {
-    let mut return_vec = Vec::new();
-    unsafe {
-        for i in 0..data.len() {
-            return_vec.push(&mut *(data.get_unchecked_mut(i) as *mut T)); 
+impl From<toml::ser::Error> for FileError {
+    fn from(e: toml::ser::Error) -> Self {
+        FileError::Serialize(e)
+    }
+}
+
+// The xorshift128 generator behind rand's XorShiftRng, with state that can be saved.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SavableRng {
+    x: u32,
+    y: u32,
+    z: u32,
+    w: u32,
+}
+
+impl SavableRng {
+    pub fn new(seed: u32) -> Self {
+        //The state must not be all zeroes.
+        SavableRng {
+            x: 0x193a_6754,
+            y: 0xa8a7_d469,
+            z: 0x9783_0e05,
+            w: seed,
         }
     }
-    return_vec
+}
+
+impl Rng for SavableRng {
+    fn next_u32(&mut self) -> u32 {
+        let x = self.x;
+        let t = x ^ (x << 11);
+        self.x = self.y;
+        self.y = self.z;
+        self.z = self.w;
+        let w = self.w;
+        self.w = w ^ (w >> 19) ^ (t ^ (t >> 8));
+        self.w
+    }
+}
+
+// toml writes -0.0 as "--0.0", which it can't read back. Adding zero turns it into 0.0 and
+// leaves every other value alone.
+pub fn toml_float(value: f32) -> f32 {
+    value + 0.0
+}
+
+// For use with #[serde(with = "...")] on ggez types that can't derive serde.
+pub mod point_serde {
+    use ggez::graphics::Point2;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use super::toml_float;
+
+    pub fn serialize<S: Serializer>(point: &Point2, serializer: S) -> Result<S::Ok, S::Error> {
+        [toml_float(point.x), toml_float(point.y)].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Point2, D::Error> {
+        let [x, y] = <[f32; 2]>::deserialize(deserializer)?;
+        Ok(Point2::new(x, y))
+    }
+}
+
+pub mod option_point_serde {
+    use ggez::graphics::Point2;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use super::toml_float;
+
+    pub fn serialize<S: Serializer>(point: &Option<Point2>, serializer: S) -> Result<S::Ok, S::Error> {
+        point
+            .map(|p| [toml_float(p.x), toml_float(p.y)])
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Point2>, D::Error> {
+        let point = Option::<[f32; 2]>::deserialize(deserializer)?;
+        Ok(point.map(|[x, y]| Point2::new(x, y)))
+    }
+}
+
+pub mod option_vector_serde {
+    use ggez::graphics::Vector2;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use super::toml_float;
+
+    pub fn serialize<S: Serializer>(vector: &Option<Vector2>, serializer: S) -> Result<S::Ok, S::Error> {
+        vector
+            .map(|v| [toml_float(v.x), toml_float(v.y)])
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vector2>, D::Error> {
+        let vector = Option::<[f32; 2]>::deserialize(deserializer)?;
+        Ok(vector.map(|[x, y]| Vector2::new(x, y)))
+    }
+}
+
+pub mod point_vec_serde {
+    use ggez::graphics::Point2;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use super::toml_float;
+
+    pub fn serialize<S: Serializer>(points: &Vec<Point2>, serializer: S) -> Result<S::Ok, S::Error> {
+        points
+            .iter()
+            .map(|p| [toml_float(p.x), toml_float(p.y)])
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Point2>, D::Error> {
+        let points = Vec::<[f32; 2]>::deserialize(deserializer)?;
+        Ok(points.iter().map(|&[x, y]| Point2::new(x, y)).collect())
+    }
+}
+
+pub mod vector_serde {
+    use ggez::graphics::Vector2;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use super::toml_float;
+
+    pub fn serialize<S: Serializer>(vector: &Vector2, serializer: S) -> Result<S::Ok, S::Error> {
+        [toml_float(vector.x), toml_float(vector.y)].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vector2, D::Error> {
+        let [x, y] = <[f32; 2]>::deserialize(deserializer)?;
+        Ok(Vector2::new(x, y))
+    }
+}
+
+pub mod color_serde {
+    use ggez::graphics::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        [color.r, color.g, color.b, color.a].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let [r, g, b, a] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(Color::new(r, g, b, a))
+    }
 }
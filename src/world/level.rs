@@ -0,0 +1,309 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use ggez::graphics::Point2;
+use toml;
+use game_object::collision::cross;
+use utils::FileError;
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spawn {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Spawn {
+    pub fn get_position(&self) -> Point2 {
+        Point2::new(self.x, self.y)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MobKind {
+    // Runs at the player and hurts them on contact.
+    Dummy,
+    // Keeps its distance and shoots at the player.
+    Shooter,
+}
+
+impl Default for MobKind {
+    fn default() -> Self {
+        MobKind::Dummy
+    }
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MobSpawn {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub kind: MobKind,
+}
+
+impl MobSpawn {
+    pub fn get_position(&self) -> Point2 {
+        Point2::new(self.x, self.y)
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockDef {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    // Leave out for an indestructible block.
+    #[serde(default)]
+    pub health: Option<i32>,
+    // 0 stops whatever hits it dead, 1 bounces it back at full speed.
+    #[serde(default)]
+    pub elasticity: f32,
+}
+
+// A convex obstacle, for walls and cover at an angle.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolygonDef {
+    // The corners as [x, y], in order around the outside, either way round.
+    pub points: Vec<[f32; 2]>,
+    #[serde(default)]
+    pub health: Option<i32>,
+    #[serde(default)]
+    pub elasticity: f32,
+}
+
+impl PolygonDef {
+    pub fn get_points(&self) -> Vec<Point2> {
+        self.points
+            .iter()
+            .map(|point| Point2::new(point[0], point[1]))
+            .collect()
+    }
+
+    // Every corner has to turn the same way, which also rules out repeated and collinear corners.
+    fn is_convex(&self) -> bool {
+        let points = self.get_points();
+        let count = points.len();
+        if count < 3 {
+            return false;
+        }
+        let mut turns = Vec::new();
+        for i in 0..count {
+            let (a, b, c) = (points[i], points[(i + 1) % count], points[(i + 2) % count]);
+            turns.push(cross(b - a, c - b));
+        }
+        turns.iter().all(|turn| *turn > 0.0) || turns.iter().all(|turn| *turn < 0.0)
+    }
+}
+
+// Some mobs of one kind in a wave.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaveGroup {
+    pub kind: MobKind,
+    pub count: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaveDef {
+    // Spawned in order, one group after another.
+    pub groups: Vec<WaveGroup>,
+}
+
+// Waves of mobs for survival mode. Once the last wave has been beaten it's repeated for every
+// wave after, which keeps getting harder through the growth rates.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WavesDef {
+    // Where wave mobs appear, picked at random for each mob.
+    pub spawn_points: Vec<Spawn>,
+    // Seconds from the last mob of a wave dying to the next wave starting, and before the first.
+    pub break_time: f32,
+    // Seconds between one mob of a wave spawning and the next.
+    pub spawn_interval: f32,
+    // How much each wave adds to mob count, health and acceleration, as a fraction of the first
+    // wave's. 0.25 makes wave 5 twice as big as wave 1.
+    pub count_growth: f32,
+    pub health_growth: f32,
+    pub speed_growth: f32,
+    pub waves: Vec<WaveDef>,
+}
+
+impl Default for WavesDef {
+    fn default() -> Self {
+        Self {
+            spawn_points: Vec::new(),
+            break_time: 5.0,
+            spawn_interval: 0.5,
+            count_growth: 0.0,
+            health_growth: 0.0,
+            speed_growth: 0.0,
+            waves: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    #[serde(default)]
+    pub player: Spawn,
+    #[serde(default)]
+    pub blocks: Vec<BlockDef>,
+    #[serde(default)]
+    pub polygons: Vec<PolygonDef>,
+    #[serde(default)]
+    pub mobs: Vec<MobSpawn>,
+    // Leave out for a level without waves.
+    #[serde(default)]
+    pub waves: Option<WavesDef>,
+}
+
+impl Level {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Level, FileError> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        Level::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Level, FileError> {
+        let level: Level = toml::from_str(contents)?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    fn validate(&self) -> Result<(), FileError> {
+        for (i, block) in self.blocks.iter().enumerate() {
+            if !(block.width > 0.0 && block.height > 0.0) {
+                return Err(FileError::Invalid(format!(
+                    "block {} has a non-positive size ({} x {})",
+                    i, block.width, block.height
+                )));
+            }
+            if let Some(health) = block.health {
+                if health <= 0 {
+                    return Err(FileError::Invalid(format!(
+                        "block {} has non-positive health ({})",
+                        i, health
+                    )));
+                }
+            }
+        }
+        for (i, polygon) in self.polygons.iter().enumerate() {
+            if !polygon.is_convex() {
+                return Err(FileError::Invalid(format!(
+                    "polygon {} is not convex, or has fewer than 3 corners",
+                    i
+                )));
+            }
+            if let Some(health) = polygon.health {
+                if health <= 0 {
+                    return Err(FileError::Invalid(format!(
+                        "polygon {} has non-positive health ({})",
+                        i, health
+                    )));
+                }
+            }
+        }
+        if let Some(ref waves) = self.waves {
+            if waves.spawn_points.is_empty() || waves.waves.is_empty() {
+                return Err(FileError::Invalid(
+                    "waves need at least one spawn point and one wave".to_owned(),
+                ));
+            }
+            if !(waves.spawn_interval >= 0.0 && waves.break_time >= 0.0) {
+                return Err(FileError::Invalid(
+                    "wave spawn interval and break time can't be negative".to_owned(),
+                ));
+            }
+            for (i, wave) in waves.waves.iter().enumerate() {
+                if wave.groups.iter().all(|group| group.count == 0) {
+                    return Err(FileError::Invalid(format!("wave {} has no mobs", i + 1)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The message from parsing a level that should have been rejected.
+    fn get_error(contents: &str) -> String {
+        match Level::parse(contents) {
+            Ok(_) => panic!("level was accepted:\n{}", contents),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    const WAVES: &str = "
+[waves]
+spawn_points = [{ x = 0.0, y = 0.0 }]
+[[waves.waves]]
+groups = [{ kind = \"dummy\", count = 2 }]
+";
+
+    #[test]
+    fn levels_on_disk_are_valid() {
+        for path in &["levels/arena.toml", "levels/survival.toml"] {
+            if let Err(e) = Level::load(path) {
+                panic!("{}: {}", path, e);
+            }
+        }
+        assert!(Level::parse(WAVES).is_ok());
+    }
+
+    #[test]
+    fn bad_blocks_are_rejected() {
+        let error = get_error("[[blocks]]\nx = 0.0\ny = 0.0\nwidth = 0.0\nheight = 10.0");
+        assert_eq!(error, "block 0 has a non-positive size (0 x 10)");
+        let error = get_error(
+            "[[blocks]]\nx = 0.0\ny = 0.0\nwidth = 5.0\nheight = 5.0\n\
+             [[blocks]]\nx = 0.0\ny = 0.0\nwidth = 5.0\nheight = 5.0\nhealth = 0",
+        );
+        assert_eq!(error, "block 1 has non-positive health (0)");
+    }
+
+    #[test]
+    fn bad_polygons_are_rejected() {
+        let error = get_error("[[polygons]]\npoints = [[0.0, 0.0], [10.0, 0.0]]");
+        assert_eq!(error, "polygon 0 is not convex, or has fewer than 3 corners");
+        //A dart, with one corner turning the wrong way.
+        let error = get_error(
+            "[[polygons]]\npoints = [[0.0, 0.0], [10.0, 5.0], [0.0, 10.0], [3.0, 5.0]]",
+        );
+        assert_eq!(error, "polygon 0 is not convex, or has fewer than 3 corners");
+        let error = get_error(
+            "[[polygons]]\npoints = [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]]\nhealth = -2",
+        );
+        assert_eq!(error, "polygon 0 has non-positive health (-2)");
+    }
+
+    #[test]
+    fn bad_waves_are_rejected() {
+        let error = get_error(&WAVES.replace("spawn_points = [{ x = 0.0, y = 0.0 }]", ""));
+        assert_eq!(error, "waves need at least one spawn point and one wave");
+        let error = get_error(&WAVES.replace("[waves]", "[waves]\nbreak_time = -1.0"));
+        assert_eq!(error, "wave spawn interval and break time can't be negative");
+        let error = get_error(&WAVES.replace("count = 2", "count = 0"));
+        assert_eq!(error, "wave 1 has no mobs");
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let error = get_error("[player]\nx = 0.0\ny = 0.0\nz = 0.0");
+        assert!(error.starts_with("could not parse file: "), "{}", error);
+        assert!(error.contains("unknown field `z`"), "{}", error);
+        let error = get_error("[[blocks]]\nx = 0.0\ny = 0.0\nwidth = 5.0\nheight = 5.0\nhue = 1");
+        assert!(error.contains("unknown field `hue`"), "{}", error);
+        let error = get_error("[[mobs]]\nx = 0.0\ny = 0.0\nkind = \"dragon\"");
+        assert!(error.contains("unknown variant `dragon`"), "{}", error);
+    }
+}
@@ -0,0 +1,630 @@
+use std::f32;
+use rand::Rng;
+use ggez::graphics::{Point2, Vector2};
+use game_object;
+use game_object::*;
+use game_object::block::Block;
+use game_object::mob::*;
+use game_object::behaviour::{BehaviourState, HasBehaviour, Senses};
+use game_object::bullet::Bullet;
+use game_object::broadphase::{SpatialHash, CELL_SIZE};
+use game_object::shooter::Shooter;
+use game_object::steering::{avoidance, cohesion, get_feelers, separation};
+use game_object::raycast::{raycast_object, raycast_vec, Category, RayHit};
+use utils::SavableRng;
+use config::{GameplayConfig, Navigation};
+
+pub mod level;
+pub mod navigation;
+pub mod replay;
+pub mod snapshot;
+pub mod waves;
+
+use self::level::{Level, MobKind};
+use self::navigation::{FlowField, NavGrid, NAV_CLEARANCE};
+use self::waves::WaveDirector;
+
+// How far a patrolling mob wanders from where it starts.
+const PATROL_RADIUS: f32 = 60.0;
+
+// A snapshot of everything the player is asking for during one tick.
+#[derive(Clone, Copy)]
+pub struct PlayerInput {
+    pub movement: Vector2,
+    pub shoot_direction: Vector2,
+    pub shooting: bool,
+    // The index of the weapon to switch to before shooting, if any.
+    pub switch_weapon: Option<usize>,
+    pub reload: bool,
+    pub reset: bool,
+}
+
+// All of the gameplay state. Has no knowledge of ggez's Context, so it can be stepped without a
+// window. Serializes to a complete snapshot of the game.
+#[derive(Serialize, Deserialize)]
+pub struct World {
+    level: Level,
+    config: GameplayConfig,
+    // All randomness in the simulation must come from here to keep replays deterministic.
+    rng: SavableRng,
+    player_mob: player::Player,
+    mobs: Vec<Mob>,
+    blocks: Vec<Block>,
+    projectiles: Vec<Bullet>,
+    // Only for levels with waves.
+    #[serde(default)]
+    waves: Option<WaveDirector>,
+    // Rebuilt from the blocks whenever they change, see build_navigation.
+    #[serde(skip)]
+    navigation: NavGrid,
+    // Leads to the player, and is rebuilt when they move to another cell. Only used by flow field
+    // navigation.
+    #[serde(skip)]
+    flow_field: FlowField,
+}
+
+impl World {
+    // Worlds built from the same level, config and seed, and fed the same inputs, play out
+    // identically.
+    pub fn new(level: Level, config: GameplayConfig, seed: u32) -> Self {
+        let mut world = World {
+            rng: SavableRng::new(seed),
+            player_mob: player::Player::new(level.player.get_position(), &config.player),
+            level: Level::default(),
+            config,
+            mobs: Vec::new(),
+            blocks: Vec::new(),
+            projectiles: Vec::new(),
+            waves: level.waves.as_ref().map(WaveDirector::new),
+            navigation: NavGrid::default(),
+            flow_field: FlowField::default(),
+        };
+        world.load_level(level);
+        world
+    }
+
+    // Replace the level geometry. Mobs and the player are only moved on the next reset.
+    pub fn load_level(&mut self, level: Level) {
+        self.level = level;
+        self.build_blocks();
+    }
+
+    fn build_blocks(&mut self) {
+        self.blocks = self.level
+            .blocks
+            .iter()
+            .map(|b| {
+                let block = Block::with_size(b.x, b.y, b.width, b.height)
+                    .with_elasticity(b.elasticity);
+                match b.health {
+                    Some(health) => block.with_health(health),
+                    None => block,
+                }
+            })
+            .collect();
+        for (i, p) in self.level.polygons.iter().enumerate() {
+            let block = Block::polygon(i, &p.get_points()).with_elasticity(p.elasticity);
+            self.blocks.push(match p.health {
+                Some(health) => block.with_health(health),
+                None => block,
+            });
+        }
+        self.build_navigation();
+    }
+
+    pub fn build_navigation(&mut self) {
+        // Everywhere something starts has to be on the grid, even outside the blocks.
+        let mut points = vec![self.level.player.get_position()];
+        points.extend(self.level.mobs.iter().map(|spawn| spawn.get_position()));
+        if let Some(ref waves) = self.level.waves {
+            points.extend(waves.spawn_points.iter().map(|spawn| spawn.get_position()));
+        }
+        self.navigation = NavGrid::new(&self.blocks, &points, NAV_CLEARANCE);
+        self.flow_field = FlowField::default();
+    }
+
+    pub fn get_player(&self) -> &player::Player {
+        &self.player_mob
+    }
+
+    pub fn get_mobs(&self) -> &Vec<Mob> {
+        &self.mobs
+    }
+
+    pub fn get_blocks(&self) -> &Vec<Block> {
+        &self.blocks
+    }
+
+    pub fn get_projectiles(&self) -> &Vec<Bullet> {
+        &self.projectiles
+    }
+
+    // The current wave, counting from 1, or None if the level doesn't have waves.
+    pub fn get_wave(&self) -> Option<u32> {
+        self.waves.as_ref().map(|waves| waves.get_wave())
+    }
+
+    // The first object in one of categories on the line from `from` to `to`. Objects the line
+    // starts inside of are skipped.
+    pub fn raycast(&self, from: Point2, to: Point2, categories: &[Category]) -> Option<RayHit> {
+        let delta = to - from;
+        let mut first = None;
+        for category in categories {
+            let hit = match *category {
+                Category::Player => raycast_object(from, delta, &self.player_mob)
+                    .map(|(time, normal)| {
+                        RayHit::new(Category::Player, 0, time, from + time * delta, normal)
+                    }),
+                Category::Mob => raycast_vec(from, delta, &self.mobs, Category::Mob),
+                Category::Block => raycast_vec(from, delta, &self.blocks, Category::Block),
+            };
+            first = RayHit::first(first, hit);
+        }
+        first
+    }
+
+    // Whether no block is in the way between the two points.
+    pub fn has_line_of_sight(&self, from: Point2, to: Point2) -> bool {
+        self.raycast(from, to, &[Category::Block]).is_none()
+    }
+
+    // Whether the player is the first thing on the line from `from` to their center.
+    pub fn can_see_player(&self, from: Point2) -> bool {
+        let target = self.player_mob.get_center_position();
+        self.raycast(from, target, &[Category::Block, Category::Player])
+            .map_or(false, |hit| hit.get_category() == Category::Player)
+    }
+
+    // Advance the simulation by one fixed step.
+    pub fn update(&mut self, dt: f32, input: &PlayerInput) {
+        if input.reset {
+            self.reset();
+        }
+        self.handle_player_input(input);
+        self.calculate_step(dt);
+        self.calculate_collision_events(dt);
+        self.calculate_physics(dt);
+        self.calculate_ai(dt);
+        self.calculate_steering();
+        self.calculate_mob_shots();
+        self.clear_objects();
+        self.spawn_waves(dt);
+    }
+
+    pub fn reset(&mut self) {
+        self.player_mob = player::Player::new(self.level.player.get_position(), &self.config.player);
+        self.mobs.drain(..);
+        self.projectiles.drain(..);
+        self.build_blocks();
+        for spawn in &self.level.mobs {
+            let mob = self.make_mob(spawn.kind, spawn.get_position(), 1.0, 1.0);
+            self.mobs.push(mob);
+        }
+        self.waves = self.level.waves.as_ref().map(WaveDirector::new);
+    }
+
+    // A mob of kind at position, with its health and acceleration scaled up for later waves.
+    fn make_mob(&self, kind: MobKind, position: Point2, health_scale: f32, speed_scale: f32) -> Mob {
+        let scale_health = |health: i32| ((health as f32 * health_scale).round() as i32).max(1);
+        match kind {
+            MobKind::Dummy => {
+                let mut config = self.config.mob.clone();
+                config.health = scale_health(config.health);
+                config.acceleration *= speed_scale;
+                Mob::Dummy(Dummy::new(position, &config))
+            }
+            MobKind::Shooter => {
+                let mut config = self.config.shooter.clone();
+                config.health = scale_health(config.health);
+                config.acceleration *= speed_scale;
+                Mob::Shooter(Shooter::new(position, &config))
+            }
+        }
+    }
+
+    // Spawn whatever the wave director asks for, each at a random one of the spawn points.
+    fn spawn_waves(&mut self, dt: f32) {
+        let (spawns, health_scale, speed_scale) = match (&mut self.waves, &self.level.waves) {
+            (&mut Some(ref mut director), &Some(ref waves)) => (
+                director.update(dt, waves, self.mobs.len()),
+                director.get_scale(waves.health_growth),
+                director.get_scale(waves.speed_growth),
+            ),
+            _ => return,
+        };
+        for kind in spawns {
+            let position = match self.level.waves {
+                Some(ref waves) => {
+                    let index = self.rng.gen_range(0, waves.spawn_points.len());
+                    waves.spawn_points[index].get_position()
+                }
+                None => return,
+            };
+            let mob = self.make_mob(kind, position, health_scale, speed_scale);
+            self.mobs.push(mob);
+        }
+    }
+
+    fn handle_player_input(&mut self, input: &PlayerInput) {
+        self.player_mob.set_movement(input.movement);
+        self.player_mob.set_shoot_direction(input.shoot_direction);
+        if let Some(index) = input.switch_weapon {
+            self.player_mob.switch_weapon(index);
+        }
+        if input.reload {
+            self.player_mob.reload();
+        }
+        if input.shooting {
+            let projectiles = self.player_mob.shoot(&mut self.rng);
+            self.projectiles.extend(projectiles);
+        }
+    }
+
+    fn calculate_step(&mut self, dt: f32) {
+        self.player_mob.step(dt);
+        for object in &mut self.mobs {
+            object.step(dt);
+        }
+        for object in &mut self.blocks {
+            object.step(dt);
+        }
+        for object in &mut self.projectiles {
+            object.step(dt);
+        }
+    }
+
+    fn clear_objects(&mut self) {
+        self.projectiles
+            .retain(|ref projectile| !projectile.should_delete());
+        self.mobs.retain(|ref mob| !mob.should_delete());
+        let block_count = self.blocks.len();
+        self.blocks.retain(|ref block| !block.should_delete());
+        if self.blocks.len() != block_count {
+            self.build_navigation();
+        }
+        if self.player_mob.should_delete() {
+            self.player_mob = player::Player::new(self.level.player.get_position(), &self.config.player);
+            self.mobs.drain(..);
+            self.projectiles.drain(..);
+            self.waves = self.level.waves.as_ref().map(WaveDirector::new);
+        }
+    }
+
+    // Each mob updates its behaviour state from what it can sense, then acts on that state. Mobs
+    // that can shoot are only given the player to aim at while attacking them in plain sight.
+    fn calculate_ai(&mut self, dt: f32) {
+        let target = self.player_mob.get_center_position();
+        let use_flow_field = self.config.mob.navigation == Navigation::FlowField;
+        if use_flow_field && self.flow_field.get_goal() != Some(self.navigation.get_cell(target)) {
+            self.flow_field = FlowField::new(&self.navigation, target);
+        }
+        for i in 0..self.mobs.len() {
+            let center = self.mobs[i].get_center_position();
+            let can_see_player = self.can_see_player(center);
+            let senses = Senses {
+                distance: (target - center).norm(),
+                can_see_player,
+                health: self.mobs[i].get_health_fraction(),
+                stunned: self.mobs[i].is_stunned(),
+                attack_range: self.mobs[i].get_attack_range(),
+            };
+            let transitions = self.mobs[i].get_transitions();
+            let changed = self.mobs[i]
+                .get_behaviour_mut()
+                .update(dt, &senses, transitions);
+            match self.mobs[i].get_behaviour().get_state() {
+                BehaviourState::Idle | BehaviourState::Stunned => {
+                    self.mobs[i].set_heading(Vector2::zeros())
+                }
+                BehaviourState::Patrol => if changed {
+                    self.start_patrol(i, center)
+                },
+                BehaviourState::Chase => self.chase(i, center, can_see_player, use_flow_field),
+                BehaviourState::Attack => self.attack(i, center),
+                BehaviourState::Flee => self.mobs[i].set_heading(center - target),
+            }
+            let aim = match self.mobs[i].get_behaviour().get_state() {
+                BehaviourState::Attack if can_see_player => {
+                    Some((target, self.player_mob.get_velocity()))
+                }
+                _ => None,
+            };
+            self.mobs[i].set_aim(aim);
+        }
+    }
+
+    // Mobs that fight up close run right at the player. The rest back off or close in to their
+    // preferred distance, slowing down as they get near it.
+    fn attack(&mut self, i: usize, center: Point2) {
+        let preferred = self.mobs[i].get_preferred_distance();
+        if preferred <= 0.0 {
+            self.mobs[i].set_target(self.player_mob.get_center_position());
+            return;
+        }
+        let offset = self.player_mob.get_center_position() - center;
+        let distance = offset.norm();
+        if distance > 0.0 {
+            self.mobs[i].set_heading((distance - preferred) / preferred * offset / distance);
+        }
+    }
+
+    // Work out the pull of each mob's surroundings: away from crowding neighbours, towards the
+    // middle of the group, and away from walls its feelers touch.
+    fn calculate_steering(&mut self) {
+        let grid = SpatialHash::from_list(CELL_SIZE, &self.mobs);
+        let centers: Vec<Point2> = self.mobs.iter().map(|mob| mob.get_center_position()).collect();
+        for i in 0..self.mobs.len() {
+            let (center, steering) = (centers[i], self.mobs[i].get_steering());
+            let reach = Vector2::new(steering.neighbour_radius, steering.neighbour_radius);
+            let neighbours: Vec<Point2> = grid.query(center - reach, 2.0 * reach)
+                .into_iter()
+                .filter(|&j| j != i)
+                .map(|j| centers[j])
+                .collect();
+            let hits: Vec<RayHit> = get_feelers(self.mobs[i].get_velocity())
+                .into_iter()
+                .filter_map(|feeler| self.raycast(center, center + feeler, &[Category::Block]))
+                .collect();
+            let force = steering.separation
+                * separation(center, &neighbours, steering.neighbour_radius)
+                + steering.cohesion * cohesion(center, &neighbours, steering.neighbour_radius)
+                + steering.avoidance * avoidance(&hits);
+            self.mobs[i].set_steering_force(force);
+        }
+    }
+
+    fn calculate_mob_shots(&mut self) {
+        for mob in &mut self.mobs {
+            let projectiles = mob.shoot(&mut self.rng);
+            self.projectiles.extend(projectiles);
+        }
+    }
+
+    // Wander to a random nearby spot the mob can see, or stay put if it picked one behind a wall.
+    fn start_patrol(&mut self, i: usize, center: Point2) {
+        let angle = self.rng.gen_range(0.0, 2.0 * f32::consts::PI);
+        let distance = self.rng.gen_range(0.0, PATROL_RADIUS);
+        let spot = center + distance * Vector2::new(angle.cos(), angle.sin());
+        if self.has_line_of_sight(center, spot) {
+            self.mobs[i].set_target(spot);
+        } else {
+            self.mobs[i].set_heading(Vector2::zeros());
+        }
+    }
+
+    // Mobs chase the player while they can see them. Otherwise they either follow the flow field
+    // to the player, or find a path to where they last saw them. Paths are only searched for again
+    // once that spot moves to another cell.
+    fn chase(&mut self, i: usize, center: Point2, can_see_player: bool, use_flow_field: bool) {
+        if can_see_player {
+            self.mobs[i].set_target(self.player_mob.get_center_position());
+            return;
+        }
+        if use_flow_field {
+            match self.flow_field.get_direction(&self.navigation, center) {
+                Some(heading) => self.mobs[i].set_heading(heading),
+                None => self.mobs[i].set_target(self.player_mob.get_center_position()),
+            }
+            return;
+        }
+        let last_seen = match self.mobs[i].get_target() {
+            Some(last_seen) => last_seen,
+            None => return,
+        };
+        let goal = self.navigation.get_cell(last_seen);
+        let path_goal = self.mobs[i]
+            .get_path_target()
+            .map(|path_target| self.navigation.get_cell(path_target));
+        if path_goal != Some(goal) {
+            let path = self.navigation
+                .find_path(center, last_seen)
+                .unwrap_or_else(Vec::new);
+            self.mobs[i].set_path(last_seen, path);
+        }
+    }
+
+    fn calculate_physics(&mut self, dt: f32) {
+        collision::vec_vec_physics(dt, &mut self.mobs, &mut self.blocks);
+        collision::vec_physics(dt, &mut self.mobs);
+        collision::object_vec_physics(dt, &mut self.player_mob, &mut self.blocks);
+        collision::object_vec_physics(dt, &mut self.player_mob, &mut self.mobs);
+    }
+
+    fn calculate_collision_events(&mut self, dt: f32) {
+        game_object::vec_vec_first_hits(&self.mobs, &mut self.projectiles);
+        game_object::object_vec_first_hits(&self.player_mob, &mut self.projectiles);
+        game_object::vec_vec_first_hits(&self.blocks, &mut self.projectiles);
+        game_object::vec_vec_collision_events(dt, &mut self.mobs, &mut self.projectiles);
+        game_object::vec_vec_collision_events(dt, &mut self.blocks, &mut self.projectiles);
+        game_object::object_vec_collision_events(dt, &mut self.player_mob, &mut self.projectiles);
+        game_object::object_vec_collision_events(dt, &mut self.player_mob, &mut self.mobs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use super::*;
+    use super::replay::{Replay, ReplayPlayback};
+
+    const DT: f32 = 1.0 / 60.0;
+
+    // A minute of play at 60 ticks a second.
+    const TICKS: u32 = 3600;
+
+    // Runs in circles, firing all around and switching weapons now and then, so that the mobs,
+    // bullets, reloads and waves all get exercised.
+    fn get_input(tick: u32) -> PlayerInput {
+        let angle = tick as f32 * 0.05;
+        PlayerInput {
+            movement: Vector2::new(angle.cos(), angle.sin()),
+            shoot_direction: Vector2::new((3.0 * angle).cos(), (3.0 * angle).sin()),
+            shooting: tick % 240 < 180,
+            switch_weapon: if tick % 600 == 0 { Some((tick / 600) as usize % 3) } else { None },
+            reload: false,
+            reset: tick == 0,
+        }
+    }
+
+    fn soak(level_path: &str) -> World {
+        let level = Level::load(level_path).unwrap();
+        let mut world = World::new(level, GameplayConfig::default(), 7);
+        for tick in 0..TICKS {
+            world.update(DT, &get_input(tick));
+            let player = world.get_player().get_position();
+            assert!(player.x.is_finite() && player.y.is_finite(), "player lost at {}", tick);
+            for mob in world.get_mobs() {
+                let position = mob.get_position();
+                assert!(position.x.is_finite() && position.y.is_finite(), "mob lost at {}", tick);
+            }
+        }
+        world
+    }
+
+    #[test]
+    fn arena_runs_headless() {
+        soak("levels/arena.toml");
+    }
+
+    #[test]
+    fn survival_runs_headless() {
+        let world = soak("levels/survival.toml");
+        assert!(world.get_wave().unwrap() >= 1);
+    }
+
+    // A player, then a mob, then a wall, then another mob, all in a row along y = 5.
+    fn row() -> World {
+        let level = Level::parse(
+            r#"
+            [player]
+            x = 0.0
+            y = 0.0
+
+            [[blocks]]
+            x = 60.0
+            y = -20.0
+            width = 10.0
+            height = 50.0
+
+            [[mobs]]
+            x = 40.0
+            y = 0.0
+
+            [[mobs]]
+            x = 100.0
+            y = 0.0
+            "#,
+        ).unwrap();
+        let mut world = World::new(level, GameplayConfig::default(), 3);
+        world.reset();
+        world
+    }
+
+    fn assert_ray(hit: Option<RayHit>, category: Category, index: usize, point: Point2) {
+        let hit = hit.unwrap();
+        assert_eq!(hit.get_category(), category);
+        assert_eq!(hit.get_index(), index);
+        assert!((hit.get_point() - point).norm() < 1e-3, "hit {:?}", hit.get_point());
+    }
+
+    #[test]
+    fn raycast_finds_the_nearest_of_the_categories() {
+        let world = row();
+        let (from, to) = (Point2::new(-50.0, 5.0), Point2::new(200.0, 5.0));
+        let all = [Category::Block, Category::Mob, Category::Player];
+        assert_ray(world.raycast(from, to, &all), Category::Player, 0, Point2::new(0.0, 5.0));
+        let hit = world.raycast(from, to, &[Category::Mob, Category::Block]);
+        assert_ray(hit, Category::Mob, 0, Point2::new(40.0, 5.0));
+        let hit = world.raycast(from, to, &[Category::Block, Category::Mob]);
+        assert_ray(hit, Category::Mob, 0, Point2::new(40.0, 5.0));
+        assert!((hit.unwrap().get_time() - 0.36).abs() < 1e-4);
+        // The other way along the row.
+        let hit = world.raycast(to, from, &[Category::Mob]);
+        assert_ray(hit, Category::Mob, 1, Point2::new(110.0, 5.0));
+        let hit = world.raycast(to, from, &all);
+        assert_ray(hit, Category::Mob, 1, Point2::new(110.0, 5.0));
+    }
+
+    #[test]
+    fn raycast_only_hits_the_categories_asked_for() {
+        let world = row();
+        let (from, to) = (Point2::new(-50.0, 5.0), Point2::new(200.0, 5.0));
+        let hit = world.raycast(from, to, &[Category::Block]);
+        assert_ray(hit, Category::Block, 0, Point2::new(60.0, 5.0));
+        assert!(world.raycast(from, to, &[]).is_none());
+        // Stops short of everything.
+        assert!(world.raycast(from, Point2::new(-10.0, 5.0), &[Category::Player]).is_none());
+        assert!(world.has_line_of_sight(Point2::new(0.0, 40.0), Point2::new(200.0, 40.0)));
+        assert!(!world.has_line_of_sight(from, to));
+    }
+
+    #[test]
+    fn raycast_normals_point_back_at_the_ray() {
+        let world = row();
+        let (from, to) = (Point2::new(-50.0, 5.0), Point2::new(200.0, 5.0));
+        let normal = |hit: Option<RayHit>| hit.unwrap().get_normal();
+        assert_eq!(normal(world.raycast(from, to, &[Category::Player])), Vector2::new(-1.0, 0.0));
+        assert_eq!(normal(world.raycast(to, from, &[Category::Block])), Vector2::new(1.0, 0.0));
+        let (top, bottom) = (Point2::new(65.0, 100.0), Point2::new(65.0, -100.0));
+        let down = world.raycast(top, bottom, &[Category::Block]);
+        assert_ray(down, Category::Block, 0, Point2::new(65.0, 30.0));
+        assert_eq!(normal(down), Vector2::new(0.0, 1.0));
+        // Round mobs give the normal of the circle where the ray comes in.
+        let (above, center) = (Point2::new(48.0, 50.0), Point2::new(45.0, 5.0));
+        let hit = world.raycast(above, above + 2.0 * (center - above), &[Category::Mob]);
+        let point = center + 5.0 * (above - center).normalize();
+        assert_ray(hit, Category::Mob, 0, point);
+        assert!((normal(hit) - (above - center).normalize()).norm() < 1e-4);
+    }
+
+    // Playing a saved replay back has to end up in exactly the state the recorded run did.
+    #[test]
+    fn replay_matches_recorded_run() {
+        let level = Level::load("levels/arena.toml").unwrap();
+        let config = GameplayConfig::default();
+        let mut recorded = World::new(level.clone(), config.clone(), 11);
+        let mut replay = Replay::new(11, 60, level, config);
+        for tick in 0..TICKS / 4 {
+            let input = get_input(tick);
+            replay.record(&input);
+            recorded.update(DT, &input);
+        }
+        let path = env::temp_dir().join("gift_replay_test.toml");
+        replay.save(&path).unwrap();
+        let replay = Replay::load(&path).unwrap();
+
+        let mut replayed = World::new(replay.level.clone(), replay.gameplay.clone(), replay.seed);
+        let mut playback = ReplayPlayback::new(replay);
+        while let Some(input) = playback.next_input() {
+            replayed.update(DT, &input);
+        }
+        assert!(recorded.to_snapshot().unwrap() == replayed.to_snapshot().unwrap());
+    }
+
+    // A loaded snapshot has to carry on exactly as the world it was saved from would have, which
+    // also covers everything that isn't saved and is rebuilt on load, like the navigation grid.
+    #[test]
+    fn loaded_snapshot_matches_original() {
+        for &navigation in &[Navigation::Path, Navigation::FlowField] {
+            let level = Level::load("levels/survival.toml").unwrap();
+            let mut config = GameplayConfig::default();
+            config.mob.navigation = navigation;
+            let mut original = World::new(level, config, 5);
+            for tick in 0..TICKS / 4 {
+                original.update(DT, &get_input(tick));
+            }
+            let path = env::temp_dir().join("gift_snapshot_test.toml");
+            original.save_snapshot(&path).unwrap();
+            let mut loaded = World::load_snapshot(&path).unwrap();
+            assert!(original.to_snapshot().unwrap() == loaded.to_snapshot().unwrap());
+
+            for tick in TICKS / 4..TICKS / 2 {
+                let input = get_input(tick);
+                original.update(DT, &input);
+                loaded.update(DT, &input);
+            }
+            assert!(!original.get_mobs().is_empty());
+            assert!(original.to_snapshot().unwrap() == loaded.to_snapshot().unwrap());
+        }
+    }
+}
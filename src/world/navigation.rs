@@ -0,0 +1,515 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f32;
+use ggez::graphics::{Point2, Vector2};
+use game_object::{HasHitbox, Object};
+use game_object::collision::{find_penetration, Hitbox};
+
+// Side length of a navigation cell. About the size of a mob.
+pub const NAV_CELL_SIZE: f32 = 10.0;
+
+// How far a mob's center has to stay from blocks. About half a mob.
+pub const NAV_CLEARANCE: f32 = 5.0;
+
+// Cells left open around everything the grid covers, so there's a way round the outside of the
+// outer blocks.
+pub const NAV_PADDING: i32 = 2;
+
+pub type NavCell = (i32, i32);
+
+// A grid over the level marking which cells a mob can stand in. Anything off the grid counts as
+// blocked.
+pub struct NavGrid {
+    origin: Point2,
+    width: i32,
+    height: i32,
+    blocked: Vec<bool>,
+}
+
+impl Default for NavGrid {
+    fn default() -> Self {
+        NavGrid {
+            origin: Point2::new(0.0, 0.0),
+            width: 0,
+            height: 0,
+            blocked: Vec::new(),
+        }
+    }
+}
+
+//Stand-in object used to test a cell against the blocks.
+struct Probe {
+    position: Point2,
+    hitbox: Hitbox,
+}
+
+impl Object for Probe {
+    fn get_position(&self) -> Point2 {
+        self.position
+    }
+}
+
+impl HasHitbox for Probe {
+    fn get_hitbox(&self) -> &Hitbox {
+        &self.hitbox
+    }
+}
+
+//Open cells waiting to be searched, cheapest first. Ties go to the lower index so that searches
+//always come out the same.
+#[derive(Clone, Copy, PartialEq)]
+struct Node {
+    cost: f32,
+    index: usize,
+}
+
+impl Eq for Node {}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Node) -> Ordering {
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Node) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl NavGrid {
+    // A cell is blocked when a mob centered anywhere in it, reaching clearance out from its
+    // center, could overlap a block. The grid covers the blocks and points, which should include
+    // everywhere mobs and the player start, plus NAV_PADDING cells all round.
+    pub fn new<T: HasHitbox>(blocks: &[T], points: &[Point2], clearance: f32) -> Self {
+        let mut bounds = points.iter().map(|&point| (point, point)).collect::<Vec<_>>();
+        for block in blocks {
+            let position = block.get_position();
+            bounds.push((position, position + block.get_hitbox().vec()));
+        }
+        if bounds.is_empty() {
+            return NavGrid::default();
+        }
+        let (mut low, mut high) = bounds[0];
+        for &(min, max) in &bounds {
+            low = Point2::new(low.x.min(min.x), low.y.min(min.y));
+            high = Point2::new(high.x.max(max.x), high.y.max(max.y));
+        }
+        let padding = NAV_PADDING as f32 * NAV_CELL_SIZE;
+        low -= Vector2::new(padding, padding);
+        high += Vector2::new(padding, padding);
+        let width = ((high.x - low.x) / NAV_CELL_SIZE).ceil() as i32;
+        let height = ((high.y - low.y) / NAV_CELL_SIZE).ceil() as i32;
+        let mut grid = NavGrid {
+            origin: low,
+            width,
+            height,
+            blocked: vec![false; (width * height) as usize],
+        };
+
+        let grow = Vector2::new(clearance, clearance);
+        let mut probe = Probe {
+            position: low,
+            hitbox: Hitbox::new(Vector2::new(NAV_CELL_SIZE, NAV_CELL_SIZE) + 2.0 * grow),
+        };
+        for block in blocks {
+            let (position, size) = (block.get_position(), block.get_hitbox().vec());
+            let (first, last) = (grid.get_cell(position - grow), grid.get_cell(position + size + grow));
+            for x in first.0.max(0)..(last.0 + 1).min(width) {
+                for y in first.1.max(0)..(last.1 + 1).min(height) {
+                    let index = grid.get_index((x, y)).unwrap();
+                    if grid.blocked[index] {
+                        continue;
+                    }
+                    probe.position = grid.get_corner((x, y)) - grow;
+                    if find_penetration(&probe, block) != Vector2::zeros() {
+                        grid.blocked[index] = true;
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn get_cell(&self, point: Point2) -> NavCell {
+        (
+            ((point.x - self.origin.x) / NAV_CELL_SIZE).floor() as i32,
+            ((point.y - self.origin.y) / NAV_CELL_SIZE).floor() as i32,
+        )
+    }
+
+    fn get_corner(&self, cell: NavCell) -> Point2 {
+        self.origin + NAV_CELL_SIZE * Vector2::new(cell.0 as f32, cell.1 as f32)
+    }
+
+    pub fn get_center(&self, cell: NavCell) -> Point2 {
+        self.get_corner(cell) + Vector2::new(NAV_CELL_SIZE / 2.0, NAV_CELL_SIZE / 2.0)
+    }
+
+    fn get_index(&self, cell: NavCell) -> Option<usize> {
+        if cell.0 >= 0 && cell.0 < self.width && cell.1 >= 0 && cell.1 < self.height {
+            Some((cell.1 * self.width + cell.0) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn get_cell_at(&self, index: usize) -> NavCell {
+        (index as i32 % self.width, index as i32 / self.width)
+    }
+
+    pub fn is_open(&self, cell: NavCell) -> bool {
+        match self.get_index(cell) {
+            Some(index) => !self.blocked[index],
+            None => false,
+        }
+    }
+
+    // The open cell nearest to cell, searching outward a ring at a time. Cells off the grid start
+    // from the nearest edge. None if every cell is blocked.
+    pub fn find_open_cell(&self, cell: NavCell) -> Option<NavCell> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let start = (
+            cell.0.max(0).min(self.width - 1),
+            cell.1.max(0).min(self.height - 1),
+        );
+        let mut nearest: Option<(NavCell, i32)> = None;
+        for radius in 0..self.width.max(self.height) {
+            //Anything on a further ring is at least radius away.
+            if let Some((_, distance)) = nearest {
+                if distance <= radius * radius {
+                    break;
+                }
+            }
+            for dx in -radius..radius + 1 {
+                for dy in -radius..radius + 1 {
+                    if dx.abs() != radius && dy.abs() != radius {
+                        continue;
+                    }
+                    let next = (start.0 + dx, start.1 + dy);
+                    let distance = dx * dx + dy * dy;
+                    if self.is_open(next) && nearest.map_or(true, |(_, best)| distance < best) {
+                        nearest = Some((next, distance));
+                    }
+                }
+            }
+        }
+        nearest.map(|(cell, _)| cell)
+    }
+
+    // A* search from the cell holding `from` to the cell holding `to`. Returns the centers of the
+    // cells to walk through, leaving out the one `from` is in and any in the middle of a straight
+    // run, or None if there's no way through. Either end in a blocked cell is moved to the nearest
+    // open one, since mobs get pushed into walls and the player stands right next to them. A
+    // moved start is kept in the path, so the mob walks out to it first.
+    pub fn find_path(&self, from: Point2, to: Point2) -> Option<Vec<Point2>> {
+        let start = match self.find_open_cell(self.get_cell(from)) {
+            Some(start) => start,
+            None => return None,
+        };
+        let goal = match self.find_open_cell(self.get_cell(to)) {
+            Some(goal) => goal,
+            None => return None,
+        };
+        let start_index = self.get_index(start).unwrap();
+        let goal_index = self.get_index(goal).unwrap();
+
+        let mut costs = vec![f32::INFINITY; self.blocked.len()];
+        let mut came_from: Vec<Option<usize>> = vec![None; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+        costs[start_index] = 0.0;
+        open.push(Node {
+            cost: self.estimate(start, goal),
+            index: start_index,
+        });
+
+        while let Some(Node { cost, index }) = open.pop() {
+            //Left behind when a cheaper way to the cell was found.
+            if cost > costs[index] + self.estimate(self.get_cell_at(index), goal) {
+                continue;
+            }
+            if index == goal_index {
+                let mut path = self.build_path(&came_from, goal_index);
+                if start != self.get_cell(from) {
+                    path.insert(0, self.get_center(start));
+                }
+                return Some(path);
+            }
+            for (next_index, step) in self.get_neighbours(index) {
+                let cost = costs[index] + step;
+                if cost < costs[next_index] {
+                    costs[next_index] = cost;
+                    came_from[next_index] = Some(index);
+                    let next = self.get_cell_at(next_index);
+                    open.push(Node {
+                        cost: cost + self.estimate(next, goal),
+                        index: next_index,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    // The open cells a mob can step to from a cell, with the cost of the step. Moves are in eight
+    // directions, but not diagonally past the corner of a blocked cell. Moves between neighbours
+    // cost the same either way.
+    fn get_neighbours(&self, index: usize) -> Vec<(usize, f32)> {
+        let cell = self.get_cell_at(index);
+        let mut neighbours = Vec::new();
+        for dx in -1..2 {
+            for dy in -1..2 {
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+                let next = (cell.0 + dx, cell.1 + dy);
+                if !self.is_open(next) {
+                    continue;
+                }
+                if dx != 0 && dy != 0
+                    && !(self.is_open((cell.0 + dx, cell.1)) && self.is_open((cell.0, cell.1 + dy)))
+                {
+                    continue;
+                }
+                let step = if dx != 0 && dy != 0 { f32::consts::SQRT_2 } else { 1.0 };
+                neighbours.push((self.get_index(next).unwrap(), step));
+            }
+        }
+        neighbours
+    }
+
+    //Octile distance, which never overestimates on an eight way grid.
+    fn estimate(&self, from: NavCell, to: NavCell) -> f32 {
+        let dx = (from.0 - to.0).abs() as f32;
+        let dy = (from.1 - to.1).abs() as f32;
+        dx.max(dy) + (f32::consts::SQRT_2 - 1.0) * dx.min(dy)
+    }
+
+    fn build_path(&self, came_from: &[Option<usize>], goal_index: usize) -> Vec<Point2> {
+        let mut cells = vec![self.get_cell_at(goal_index)];
+        let mut index = goal_index;
+        while let Some(previous) = came_from[index] {
+            cells.push(self.get_cell_at(previous));
+            index = previous;
+        }
+        cells.reverse();
+
+        let mut path = Vec::new();
+        for i in 1..cells.len() {
+            let last = i + 1 == cells.len();
+            if !last {
+                let (a, b, c) = (cells[i - 1], cells[i], cells[i + 1]);
+                if (b.0 - a.0, b.1 - a.1) == (c.0 - b.0, c.1 - b.1) {
+                    continue;
+                }
+            }
+            path.push(self.get_center(cells[i]));
+        }
+        path
+    }
+}
+
+// For every cell, the next cell on the shortest way to one goal cell. One of these is shared by
+// every mob heading for the same place, so it costs the same however many mobs use it.
+pub struct FlowField {
+    goal: Option<NavCell>,
+    next: Vec<Option<usize>>,
+    costs: Vec<f32>,
+}
+
+impl Default for FlowField {
+    fn default() -> Self {
+        FlowField {
+            goal: None,
+            next: Vec::new(),
+            costs: Vec::new(),
+        }
+    }
+}
+
+impl FlowField {
+    // Searches outward from the goal, so each cell's cheapest neighbour is its next step. A goal in
+    // a blocked cell is searched from the nearest open one instead.
+    pub fn new(grid: &NavGrid, to: Point2) -> Self {
+        let goal = grid.get_cell(to);
+        let mut field = FlowField {
+            goal: Some(goal),
+            next: vec![None; grid.blocked.len()],
+            costs: vec![f32::INFINITY; grid.blocked.len()],
+        };
+        let goal_index = match grid.find_open_cell(goal) {
+            Some(open_goal) => grid.get_index(open_goal).unwrap(),
+            None => return field,
+        };
+
+        let mut open = BinaryHeap::new();
+        field.costs[goal_index] = 0.0;
+        open.push(Node {
+            cost: 0.0,
+            index: goal_index,
+        });
+        while let Some(Node { cost, index }) = open.pop() {
+            if cost > field.costs[index] {
+                continue;
+            }
+            for (next_index, step) in grid.get_neighbours(index) {
+                let next_cost = cost + step;
+                if next_cost < field.costs[next_index] {
+                    field.costs[next_index] = next_cost;
+                    field.next[next_index] = Some(index);
+                    open.push(Node {
+                        cost: next_cost,
+                        index: next_index,
+                    });
+                }
+            }
+        }
+        field
+    }
+
+    pub fn get_goal(&self) -> Option<NavCell> {
+        self.goal
+    }
+
+    // Which way to walk from point, towards the center of the next cell. From a blocked cell,
+    // that's whichever open neighbour is closest to the goal. None in the goal cell, and anywhere
+    // the goal can't be reached from.
+    pub fn get_direction(&self, grid: &NavGrid, point: Point2) -> Option<Vector2> {
+        let cell = grid.get_cell(point);
+        let index = match grid.get_index(cell) {
+            Some(index) => index,
+            None => return None,
+        };
+        let next = if grid.blocked[index] {
+            self.get_way_out(grid, cell)
+        } else {
+            self.next.get(index).and_then(|&next| next)
+        };
+        next.map(|next| grid.get_center(grid.get_cell_at(next)) - point)
+    }
+
+    //The open neighbour of a blocked cell with the cheapest way on to the goal.
+    fn get_way_out(&self, grid: &NavGrid, cell: NavCell) -> Option<usize> {
+        let mut best: Option<(usize, f32)> = None;
+        for dx in -1..2 {
+            for dy in -1..2 {
+                let next = (cell.0 + dx, cell.1 + dy);
+                if !grid.is_open(next) {
+                    continue;
+                }
+                let index = grid.get_index(next).unwrap();
+                let step = if dx != 0 && dy != 0 { f32::consts::SQRT_2 } else { 1.0 };
+                let cost = self.costs.get(index).map_or(f32::INFINITY, |cost| cost + step);
+                if cost.is_finite() && best.map_or(true, |(_, best)| cost < best) {
+                    best = Some((index, cost));
+                }
+            }
+        }
+        best.map(|(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use game_object::block::Block;
+    use super::*;
+
+    fn blocks() -> Vec<Block> {
+        vec![
+            Block::with_size(0.0, 0.0, 10.0, 100.0),
+            Block::with_size(45.0, 0.0, 10.0, 70.0),
+            Block::with_size(90.0, 0.0, 10.0, 100.0),
+            Block::with_size(0.0, -10.0, 100.0, 10.0),
+        ]
+    }
+
+    // Two rooms side by side on a floor, joined over the middle wall. With the clearance, the open
+    // cells inside are the columns from x = 20 to 40 and 60 to 80, from y = 10 up, and the rows
+    // from y = 80 to 100 right across. Outside the walls, the outermost ring of cells is open too.
+    fn rooms() -> NavGrid {
+        NavGrid::new(&blocks(), &[], NAV_CLEARANCE)
+    }
+
+    fn cell(grid: &NavGrid, x: f32, y: f32) -> NavCell {
+        grid.get_cell(Point2::new(x, y))
+    }
+
+    #[test]
+    fn find_open_cell_finds_the_nearest() {
+        let grid = rooms();
+        assert_eq!(grid.find_open_cell(cell(&grid, 25.0, 55.0)), Some(cell(&grid, 25.0, 55.0)));
+        //In the middle wall, whose left side is blocked further out by the clearance.
+        assert_eq!(grid.find_open_cell(cell(&grid, 55.0, 35.0)), Some(cell(&grid, 65.0, 35.0)));
+        //Off the grid, from the nearest edge.
+        assert_eq!(grid.find_open_cell(cell(&grid, 500.0, 55.0)), Some(cell(&grid, 115.0, 55.0)));
+        assert_eq!(NavGrid::default().find_open_cell((0, 0)), None);
+    }
+
+    #[test]
+    fn grid_covers_the_points_and_padding() {
+        let grid = rooms();
+        //Right round the outside of the rooms.
+        assert!(grid.is_open(cell(&grid, -15.0, -25.0)));
+        assert!(grid.is_open(cell(&grid, 115.0, 115.0)));
+        assert!(!grid.is_open(cell(&grid, -25.0, 55.0)));
+        let path = grid.find_path(Point2::new(-15.0, 55.0), Point2::new(115.0, 55.0));
+        assert!(path.is_some());
+
+        //A spawn point well away from the blocks can still be walked from.
+        let spawn = Point2::new(300.0, 50.0);
+        let grid = NavGrid::new(&blocks(), &[spawn], NAV_CLEARANCE);
+        assert!(grid.is_open(grid.get_cell(spawn)));
+        let path = grid.find_path(spawn, Point2::new(25.0, 25.0)).expect("expected a path");
+        assert_eq!(path.last(), Some(&Point2::new(25.0, 25.0)));
+    }
+
+    #[test]
+    fn find_path_moves_blocked_ends_to_open_cells() {
+        let grid = rooms();
+        //The player is up against the right of the middle wall, in a blocked cell.
+        let goal = Point2::new(56.0, 30.0);
+        assert!(!grid.is_open(grid.get_cell(goal)));
+        let path = grid.find_path(Point2::new(25.0, 25.0), goal).expect("expected a path");
+        assert_eq!(path.last(), Some(&grid.get_center(cell(&grid, 65.0, 35.0))));
+        for point in &path {
+            assert!(grid.is_open(grid.get_cell(*point)));
+        }
+        //A mob pushed into the left wall walks out of it first.
+        let start = Point2::new(12.0, 50.0);
+        assert!(!grid.is_open(grid.get_cell(start)));
+        let path = grid.find_path(start, Point2::new(25.0, 25.0)).expect("expected a path");
+        assert_eq!(path.first(), Some(&grid.get_center(cell(&grid, 25.0, 55.0))));
+    }
+
+    #[test]
+    fn flow_field_reaches_a_blocked_goal() {
+        let grid = rooms();
+        let field = FlowField::new(&grid, Point2::new(56.0, 30.0));
+        assert_eq!(field.get_goal(), Some(cell(&grid, 55.0, 35.0)));
+        //Down the left room towards the way round the wall.
+        let heading = field.get_direction(&grid, Point2::new(25.0, 25.0)).expect("no heading");
+        assert!(heading.y > 0.0);
+        //Nothing left to do in the open cell next to the goal.
+        assert!(field.get_direction(&grid, Point2::new(65.0, 35.0)).is_none());
+        assert!(field.get_direction(&grid, Point2::new(75.0, 35.0)).is_some());
+    }
+
+    #[test]
+    fn flow_field_walks_mobs_out_of_blocked_cells() {
+        let grid = rooms();
+        let field = FlowField::new(&grid, Point2::new(75.0, 35.0));
+        //Pushed into the right of the middle wall, level with the goal.
+        let point = Point2::new(57.0, 35.0);
+        assert!(!grid.is_open(grid.get_cell(point)));
+        let heading = field.get_direction(&grid, point).expect("no way out");
+        assert_eq!(point + heading, Point2::new(65.0, 35.0));
+        //Inside the outer wall has no open neighbours at all.
+        assert!(field.get_direction(&grid, Point2::new(5.0, 55.0)).is_none());
+    }
+}
@@ -0,0 +1,135 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use ggez::graphics::Vector2;
+use toml;
+use config::{validate_tick_rate, GameplayConfig};
+use utils::{toml_float, FileError};
+use super::PlayerInput;
+use super::level::Level;
+
+// A run of identical inputs lasting `ticks` steps.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct ReplayFrame {
+    ticks: u32,
+    movement: [f32; 2],
+    shoot_direction: [f32; 2],
+    shooting: bool,
+    #[serde(default)]
+    switch_weapon: Option<usize>,
+    #[serde(default)]
+    reload: bool,
+    reset: bool,
+}
+
+impl ReplayFrame {
+    fn new(input: &PlayerInput) -> Self {
+        Self {
+            ticks: 1,
+            movement: [toml_float(input.movement.x), toml_float(input.movement.y)],
+            shoot_direction: [
+                toml_float(input.shoot_direction.x),
+                toml_float(input.shoot_direction.y),
+            ],
+            shooting: input.shooting,
+            switch_weapon: input.switch_weapon,
+            reload: input.reload,
+            reset: input.reset,
+        }
+    }
+
+    fn get_input(&self) -> PlayerInput {
+        PlayerInput {
+            movement: Vector2::new(self.movement[0], self.movement[1]),
+            shoot_direction: Vector2::new(self.shoot_direction[0], self.shoot_direction[1]),
+            shooting: self.shooting,
+            switch_weapon: self.switch_weapon,
+            reload: self.reload,
+            reset: self.reset,
+        }
+    }
+
+    fn same_input(&self, other: &ReplayFrame) -> bool {
+        ReplayFrame { ticks: other.ticks, ..*self } == *other
+    }
+}
+
+// Everything needed to reproduce a session: the world it started from and the input of every
+// tick.
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u32,
+    pub tick_rate: u32,
+    pub level: Level,
+    pub gameplay: GameplayConfig,
+    frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new(seed: u32, tick_rate: u32, level: Level, gameplay: GameplayConfig) -> Self {
+        Self {
+            seed,
+            tick_rate,
+            level,
+            gameplay,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, input: &PlayerInput) {
+        let frame = ReplayFrame::new(input);
+        if let Some(last) = self.frames.last_mut() {
+            if last.same_input(&frame) {
+                last.ticks += 1;
+                return;
+            }
+        }
+        self.frames.push(frame);
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Replay, FileError> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        let replay: Replay = toml::from_str(&contents)?;
+        validate_tick_rate(replay.tick_rate).map_err(FileError::Invalid)?;
+        replay.gameplay.validate().map_err(FileError::Invalid)?;
+        Ok(replay)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), FileError> {
+        //Going through a Value lets toml put plain values before tables, which serializing the
+        //struct directly fails on when a list of tables is empty.
+        let contents = toml::to_string(&toml::Value::try_from(self)?)?;
+        File::create(path)?.write_all(contents.as_bytes())?;
+        Ok(())
+    }
+}
+
+// Feeds a replay's inputs back one tick at a time.
+pub struct ReplayPlayback {
+    replay: Replay,
+    frame: usize,
+    tick: u32,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            frame: 0,
+            tick: 0,
+        }
+    }
+
+    pub fn next_input(&mut self) -> Option<PlayerInput> {
+        while let Some(frame) = self.replay.frames.get(self.frame) {
+            if self.tick < frame.ticks {
+                self.tick += 1;
+                return Some(frame.get_input());
+            }
+            self.frame += 1;
+            self.tick = 0;
+        }
+        None
+    }
+}
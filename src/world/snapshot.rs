@@ -0,0 +1,31 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use toml;
+use utils::FileError;
+use super::World;
+
+impl World {
+    pub fn to_snapshot(&self) -> Result<String, FileError> {
+        //See Replay::save for why this goes through a Value.
+        Ok(toml::to_string(&toml::Value::try_from(self)?)?)
+    }
+
+    pub fn from_snapshot(contents: &str) -> Result<World, FileError> {
+        let mut world: World = toml::from_str(contents)?;
+        world.build_navigation();
+        Ok(world)
+    }
+
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<(), FileError> {
+        let contents = self.to_snapshot()?;
+        File::create(path)?.write_all(contents.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load_snapshot<P: AsRef<Path>>(path: P) -> Result<World, FileError> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        World::from_snapshot(&contents)
+    }
+}
@@ -0,0 +1,165 @@
+use super::level::{MobKind, WavesDef};
+
+// Runs survival mode: waits out a break, spawns the next wave a mob at a time, then waits for the
+// player to kill it before the next break.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WaveDirector {
+    // The wave being spawned or fought, counting from 1. 0 before the first wave.
+    wave: u32,
+    // Mobs of the current wave still to spawn, next first.
+    queue: Vec<MobKind>,
+    // Counts down to the next spawn while spawning, and to the next wave during a break.
+    timer: f32,
+}
+
+impl WaveDirector {
+    pub fn new(waves: &WavesDef) -> Self {
+        Self {
+            wave: 0,
+            queue: Vec::new(),
+            timer: waves.break_time,
+        }
+    }
+
+    pub fn get_wave(&self) -> u32 {
+        self.wave
+    }
+
+    // Advance by dt with mobs_left mobs alive, and return the kinds of mob to spawn this step.
+    pub fn update(&mut self, dt: f32, waves: &WavesDef, mobs_left: usize) -> Vec<MobKind> {
+        let mut spawns = Vec::new();
+        if !self.queue.is_empty() {
+            self.timer -= dt;
+            while self.timer <= 0.0 && !self.queue.is_empty() {
+                spawns.push(self.queue.remove(0));
+                self.timer += waves.spawn_interval;
+            }
+            if self.queue.is_empty() {
+                self.timer = waves.break_time;
+            }
+        } else if mobs_left > 0 {
+            self.timer = waves.break_time;
+        } else {
+            self.timer -= dt;
+            if self.timer <= 0.0 {
+                self.start_wave(waves);
+            }
+        }
+        spawns
+    }
+
+    fn start_wave(&mut self, waves: &WavesDef) {
+        self.wave += 1;
+        let index = (self.wave as usize - 1).min(waves.waves.len() - 1);
+        let scale = self.get_scale(waves.count_growth);
+        let wave = &waves.waves[index];
+        for group in &wave.groups {
+            let count = (group.count as f32 * scale).round() as u32;
+            for _ in 0..count {
+                self.queue.push(group.kind);
+            }
+        }
+        //A wave shrunk down to nothing would be skipped straight past, so it keeps one mob.
+        if self.queue.is_empty() {
+            if let Some(group) = wave.groups.iter().find(|group| group.count > 0) {
+                self.queue.push(group.kind);
+            }
+        }
+        self.timer = 0.0;
+    }
+
+    // How much bigger the current wave is than the first, given how much each wave grows by.
+    // Never goes below a tenth, whatever the growth.
+    pub fn get_scale(&self, growth: f32) -> f32 {
+        let waves_in = self.wave.max(1) - 1;
+        (1.0 + growth * waves_in as f32).max(0.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::level::{WaveDef, WaveGroup};
+
+    const DT: f32 = 0.1;
+
+    fn waves(count_growth: f32, waves: &[&[(MobKind, u32)]]) -> WavesDef {
+        WavesDef {
+            break_time: 5.0,
+            spawn_interval: 0.5,
+            count_growth,
+            waves: waves
+                .iter()
+                .map(|groups| WaveDef {
+                    groups: groups
+                        .iter()
+                        .map(|&(kind, count)| WaveGroup { kind, count })
+                        .collect(),
+                })
+                .collect(),
+            ..WavesDef::default()
+        }
+    }
+
+    // Waits for the next wave to start and returns everything it spawns.
+    fn next_wave(director: &mut WaveDirector, waves: &WavesDef) -> Vec<MobKind> {
+        let wave = director.get_wave();
+        let mut spawned = Vec::new();
+        for _ in 0..1000 {
+            spawned.extend(director.update(DT, waves, spawned.len()));
+            if director.get_wave() != wave && director.queue.is_empty() {
+                return spawned;
+            }
+        }
+        panic!("wave {} never finished spawning", wave + 1);
+    }
+
+    #[test]
+    fn waves_wait_for_the_break_after_the_last_mob_dies() {
+        let waves = waves(0.0, &[&[(MobKind::Dummy, 2)]]);
+        let mut director = WaveDirector::new(&waves);
+        assert!(director.update(4.0, &waves, 0).is_empty());
+        assert_eq!(director.get_wave(), 0);
+        assert!(director.update(1.0, &waves, 0).is_empty());
+        assert_eq!(director.get_wave(), 1);
+        //The wave comes out a mob at a time.
+        assert_eq!(director.update(DT, &waves, 0).len(), 1);
+        assert_eq!(director.update(0.2, &waves, 1).len(), 0);
+        assert_eq!(director.update(0.3, &waves, 1).len(), 1);
+        //No break while any of it is left alive, however long that takes.
+        assert!(director.update(60.0, &waves, 1).is_empty());
+        assert!(director.update(4.9, &waves, 0).is_empty());
+        assert_eq!(director.get_wave(), 1);
+        director.update(0.2, &waves, 0);
+        assert_eq!(director.get_wave(), 2);
+    }
+
+    #[test]
+    fn waves_grow_by_count_growth() {
+        let waves = waves(0.5, &[&[(MobKind::Dummy, 2)]]);
+        let mut director = WaveDirector::new(&waves);
+        let counts: Vec<usize> = (0..4).map(|_| next_wave(&mut director, &waves).len()).collect();
+        assert_eq!(counts, vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn last_wave_repeats() {
+        let waves = waves(0.0, &[&[(MobKind::Dummy, 1)], &[(MobKind::Shooter, 2)]]);
+        let mut director = WaveDirector::new(&waves);
+        assert!(next_wave(&mut director, &waves) == vec![MobKind::Dummy]);
+        for wave in 2..5 {
+            assert!(next_wave(&mut director, &waves) == vec![MobKind::Shooter; 2]);
+            assert_eq!(director.get_wave(), wave);
+        }
+    }
+
+    #[test]
+    fn shrinking_waves_keep_a_mob() {
+        let waves = waves(-0.5, &[&[(MobKind::Shooter, 0), (MobKind::Dummy, 1)]]);
+        let mut director = WaveDirector::new(&waves);
+        for wave in 1..5 {
+            assert!(next_wave(&mut director, &waves) == vec![MobKind::Dummy]);
+            assert_eq!(director.get_wave(), wave);
+        }
+    }
+}